@@ -8,9 +8,16 @@ mod rubiks_render;
 use solver::RubiksCubeSolver;
 
 use std::time::Instant;
+use std::collections::HashMap;
 
 use std::io;
 
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(target_family = "unix")]
+use nix::sys::signal::{SigSet, Signal};
+
 fn time_solves()
 {
     // time heuristics table
@@ -77,6 +84,44 @@ fn time_solves()
     }
 }
 
+/// Makes Ctrl-C set `cancel` while `solving` is set and quit otherwise. SIGINT is blocked and one thread waits for it,
+/// so this has to be called before any other threads are made (they inherit the blocked signal).
+#[cfg(target_family = "unix")]
+fn cancel_on_ctrl_c(cancel: Arc<AtomicBool>, solving: Arc<AtomicBool>)
+{
+    let mut sigint = SigSet::empty();
+    sigint.add(Signal::SIGINT);
+    if let Err(err) = sigint.thread_block()
+    {
+        println!("Can't cancel solves with Ctrl-C: {:?}", err);
+        return;
+    }
+
+    std::thread::spawn(move ||
+    {
+        loop
+        {
+            if sigint.wait().is_ok()
+            {
+                if solving.load(Ordering::Relaxed)
+                {
+                    println!("Cancelling the solve.");
+                    cancel.store(true, Ordering::Relaxed);
+                }
+                else
+                {
+                    std::process::exit(130);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(not(target_family = "unix"))]
+fn cancel_on_ctrl_c(_cancel: Arc<AtomicBool>, _solving: Arc<AtomicBool>)
+{
+}
+
 fn solve_given(show_cubes: bool)
 {
     // wwoowwbgrgbybggygroogrrrgrrygybbywwogoooowbybwybyyrrbw
@@ -91,9 +136,19 @@ fn solve_given(show_cubes: bool)
 
     //let mut solver = RubiksCubeSolver::from_state(rubiks::RubiksCubeState::std_solved_nxnxn(2));
     let mut solver = RubiksCubeSolver::new();
-    let t0 = Instant::now();
-    solver.calc_new_heuristics_table();
-    println!("Done calculating heuristics table in {} secs.", t0.elapsed().as_secs_f64());
+
+    // Ctrl-C cancels the solve instead of quitting
+    let cancel = Arc::new(AtomicBool::new(false));
+    let solving = Arc::new(AtomicBool::new(false));
+    solver.set_cancel_flag(cancel.clone());
+    cancel_on_ctrl_c(cancel.clone(), solving.clone());
+
+    // The one solver is used for every input, whatever the size. Its corner table is built on the first input that needs
+    // it (every size but 1x1x1) and kept for the rest. There are no edge tables yet, once there are they should be built
+    // the same way on the first 3x3x3.
+    let mut table_built = false;
+    // The first solve time of each size is kept to compare the later ones with, and if it included building the table.
+    let mut first_solve_secs: HashMap<usize, (f64, bool)> = HashMap::new();
 
     loop
     {
@@ -118,6 +173,17 @@ fn solve_given(show_cubes: bool)
                     }
                 }
 
+                let t0 = Instant::now();
+                let builds_table = !table_built && input_state.size() >= 2;
+                if builds_table
+                {
+                    solver.calc_new_heuristics_table();
+                    table_built = true;
+                    println!("Done calculating heuristics table in {} secs.", t0.elapsed().as_secs_f64());
+                }
+
+                cancel.store(false, Ordering::Relaxed);
+                solving.store(true, Ordering::Relaxed);
                 if input_state.size() == 2
                 {
                     match solver.solver_2x2x2_with_heuristics_table(&input_state)
@@ -135,6 +201,22 @@ fn solve_given(show_cubes: bool)
                         Err(err) => println!("No Solution: {:?}", err),
                     }
                 }
+                solving.store(false, Ordering::Relaxed);
+
+                let secs = t0.elapsed().as_secs_f64();
+                let n = input_state.size();
+                let with_table = |built: bool| if built { " with building the table" } else { "" };
+                match first_solve_secs.get(&n)
+                {
+                    Some(&(first, built)) if secs > 0.0 =>
+                        println!("Took {} secs, the first {1}x{1}x{1} took {2} secs{3} ({4:.2}x faster).", secs, n, first, with_table(built), first / secs),
+                    Some(_) => println!("Took {} secs.", secs),
+                    None =>
+                    {
+                        println!("Took {} secs{}.", secs, with_table(builds_table));
+                        first_solve_secs.insert(n, (secs, builds_table));
+                    },
+                }
             }
             Err(error) => println!("error: {}", error),
         }
@@ -209,7 +291,7 @@ fn test_draw()
 
     t = tb * a_1;
     
-    println!("{}\n{:?}", t,state);
+    println!("{}\n{}\n{:?}", t, t.to_axis_notation(), state);
     rubiks_render::RubikDrawer::from_state(state.clone()).show();
     for turn in t
     {
@@ -239,11 +321,59 @@ fn test_draw()
     }
     // state.do_move(&soln);
 
-    println!("{}\n{:?}\nsolved: {}", soln, state, state.is_solved());
+    println!("{}\n{}\n{:?}\nsolved: {}", soln, soln.to_axis_notation(), state, state.is_solved());
+}
+
+/// `selftest [--size N] [--trials T] [--length L] [--seed S]` scrambles `T` cubes with `L` random turns, solves them
+/// with ida* and checks the solutions. Prints how many turns the solutions took and returns false if any failed.
+/// The same seed always gives the same scrambles.
+fn selftest(args: &[String]) -> bool
+{
+    let (mut size, mut trials, mut length, mut seed) = (2, 10, None, 0);
+    for option in args.chunks(2)
+    {
+        let value = option.get(1).and_then(|value| value.parse::<u64>().ok());
+        match (option[0].as_str(), value)
+        {
+            ("--size", Some(value)) if value >= 2 => size = value as usize,
+            ("--trials", Some(value)) => trials = value as usize,
+            ("--length", Some(value)) => length = Some(value as usize),
+            ("--seed", Some(value)) => seed = value,
+            _ =>
+            {
+                println!("usage: selftest [--size N] [--trials T] [--length L] [--seed S]");
+                return false;
+            }
+        }
+    }
+    // the 2x2x2 can be solved from anywhere, bigger cubes take too long to solve from a full scramble
+    let length = length.unwrap_or(if size == 2 { 100 } else { 8 });
+
+    let mut solver = RubiksCubeSolver::new();
+    let t0 = Instant::now();
+    solver.calc_new_heuristics_table();
+    println!("Done calculating heuristics table in {} secs.", t0.elapsed().as_secs_f64());
+
+    let report = solver.compare(&[solver::SolveAlgo::IdaStar], size, trials, length, seed).remove(0);
+    println!("{}x{}x{}, {} scrambles of {} turns (seed {}):", size, size, size, trials, length, seed);
+    for (len, count) in report.length_histogram()
+    {
+        println!("{:>3} turns: {}", len, count);
+    }
+    println!("solved {}, failed {}, mean {:.2} turns in {:.3} secs", report.solved, report.failed, report.mean_turns(),
+             report.mean_time().as_secs_f64());
+
+    report.failed == 0
 }
 
 fn main() 
 {
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(|arg| arg.as_str()) == Some("selftest")
+    {
+        std::process::exit(if selftest(&args[2..]) { 0 } else { 1 });
+    }
+
     time_solves();
 
     let show_cubes = std::env::args().nth(1).map(|s| s.to_lowercase().contains("show")) == Some(true);
@@ -4,6 +4,7 @@
 
 use super::rubiks;
 
+use std::collections::VecDeque;
 use std::time;
 use glium::{glutin, Surface, Display, Program, Frame, self};
 
@@ -30,16 +31,180 @@ struct GridIndex
     index: (usize, usize)
 }
 
+/// How the cube is drawn. The net is the default.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum View
+{
+    /// All 6 faces unfolded in a cross, like the `Debug` print.
+    Net,
+    /// The up, front and right faces seen from above the top front right corner.
+    Isometric,
+}
+
+/// The colors drawn around the stickers, see [`RubikDrawer::with_face_color`] and [`RubikDrawer::with_background`].
+///
+/// [`RubikDrawer::with_face_color`]: struct.RubikDrawer.html#method.with_face_color
+/// [`RubikDrawer::with_background`]: struct.RubikDrawer.html#method.with_background
+#[derive(Copy, Clone, PartialEq, Debug)]
+struct Colors
+{
+    face: (f32, f32, f32),
+    background: (f32, f32, f32),
+}
+
+impl Default for Colors
+{
+    fn default() -> Self
+    {
+        Colors{face: (0.5, 0.5, 0.5), background: (1.0, 1.0, 1.0)}
+    }
+}
+
+/// The settings of a [`RubikDrawer`] that every frame is drawn with, see [`RubikDrawer::draw`].
+///
+/// [`RubikDrawer`]: struct.RubikDrawer.html
+/// [`RubikDrawer::draw`]: struct.RubikDrawer.html#method.draw
+#[derive(Copy, Clone, PartialEq, Debug)]
+struct DrawSettings
+{
+    view: View,
+    gap: f32,
+    warn_illegal: bool,
+    colors: Colors,
+}
+
+#[derive(Clone)]
 pub struct RubikDrawer
 {
     state: rubiks::RubiksCubeState,
+    gap: Option<f32>,
+    view: View,
+    warn_illegal: bool,
+    turns: Vec<rubiks::Turn>,
+    millis_per_turn: u64,
+    smooth: bool,
+    colors: Colors,
 }
 
 impl RubikDrawer
 {
+    /// Draws any stickers you give it, even ones no cube could have. See [`from_state_checked`] to catch those.
+    ///
+    /// [`from_state_checked`]: struct.RubikDrawer.html#method.from_state_checked
     pub fn from_state(state: rubiks::RubiksCubeState) -> Self
     {
-        RubikDrawer{state}
+        RubikDrawer{state, gap: None, view: View::Net, warn_illegal: false, turns: vec![], millis_per_turn: 500, smooth: true,
+            colors: Colors::default()}
+    }
+
+    /// Same as [`from_state`] but gives `Err` if the state can't come from turning a solved cube (see
+    /// [`RubiksCubeState::is_legal`]). The `Err` still holds a drawer with [`with_illegal_warning`] on, so you can
+    /// show the bad state with a warning border around it.
+    ///
+    /// [`from_state`]: struct.RubikDrawer.html#method.from_state
+    /// [`RubiksCubeState::is_legal`]: ../rubiks/struct.RubiksCubeState.html#method.is_legal
+    /// [`with_illegal_warning`]: struct.RubikDrawer.html#method.with_illegal_warning
+    #[allow(dead_code)]
+    pub fn from_state_checked(state: rubiks::RubiksCubeState) -> Result<Self, Self>
+    {
+        if state.is_legal()
+        {
+            Ok(Self::from_state(state))
+        }
+        else
+        {
+            Err(Self::from_state(state).with_illegal_warning(true))
+        }
+    }
+
+    /// If `warn` is set, a state that isn't legal (see [`RubiksCubeState::is_legal`]) is drawn on a tinted background
+    /// with a warning border.
+    ///
+    /// [`RubiksCubeState::is_legal`]: ../rubiks/struct.RubiksCubeState.html#method.is_legal
+    #[allow(dead_code)]
+    pub fn with_illegal_warning(mut self, warn: bool) -> Self
+    {
+        self.warn_illegal = warn;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_view(mut self, view: View) -> Self
+    {
+        self.view = view;
+        self
+    }
+
+    /// Sets the color behind each face, that shows in the gaps between the stickers, as red, green and blue from 0 to 1.
+    /// The default is gray, `(0.5, 0.5, 0.5)`.
+    #[allow(dead_code)]
+    pub fn with_face_color(mut self, rgb: (f32, f32, f32)) -> Self
+    {
+        self.colors.face = rgb;
+        self
+    }
+
+    /// Sets the color of the window behind the cube, as red, green and blue from 0 to 1. The default is white,
+    /// `(1.0, 1.0, 1.0)`.
+    #[allow(dead_code)]
+    pub fn with_background(mut self, rgb: (f32, f32, f32)) -> Self
+    {
+        self.colors.background = rgb;
+        self
+    }
+
+    /// Plays `the_move` once the window opens, giving each turn `millis_per_turn` milliseconds. Panics if a turn is
+    /// for a different size cube.
+    #[allow(dead_code)]
+    pub fn with_animation(mut self, the_move: rubiks::Move, millis_per_turn: u64) -> Self
+    {
+        assert!(the_move.turns.iter().all(|turn| turn.cube_size() == self.state.size()));
+        self.turns = the_move.turns;
+        self.millis_per_turn = millis_per_turn;
+        self
+    }
+
+    /// If `smooth` is set, which is the default, the layer in [`with_animation`] visibly turns into place, otherwise
+    /// the stickers snap to where they go once the turn's time is up. Only the [`View::Net`] view turns smoothly, the
+    /// [`View::Isometric`] view always snaps.
+    ///
+    /// [`with_animation`]: struct.RubikDrawer.html#method.with_animation
+    /// [`View::Net`]: enum.View.html#variant.Net
+    /// [`View::Isometric`]: enum.View.html#variant.Isometric
+    #[allow(dead_code)]
+    pub fn with_smooth(mut self, smooth: bool) -> Self
+    {
+        self.smooth = smooth;
+        self
+    }
+
+    /// Sets the gap on each side of a sticker as a fraction of the sticker's block, so `0.0` has no gaps.
+    /// It must be less than `0.5` or there is nothing left to draw. The default is [`default_gap`].
+    ///
+    /// [`default_gap`]: struct.RubikDrawer.html#method.default_gap
+    #[allow(dead_code)]
+    pub fn with_gap(mut self, gap: f32) -> Self
+    {
+        assert!((0.0..0.5).contains(&gap));
+        self.gap = Some(gap);
+        self
+    }
+
+    /// The gap for a nxnxn cube if none is set. Bigger cubes have smaller stickers so they get a bigger fraction,
+    /// going from 0.02 for the 2x2x2 up to 0.12 from the 12x12x12 on.
+    pub fn default_gap(n: usize) -> f32
+    {
+        (n as f32 / 100.0).clamp(0.02, 0.12)
+    }
+
+    fn gap(&self) -> f32
+    {
+        self.gap.unwrap_or_else(|| Self::default_gap(self.state.size()))
+    }
+
+    fn draw_settings(&self) -> DrawSettings
+    {
+        DrawSettings{view: self.view, gap: self.gap(), warn_illegal: self.warn_illegal, colors: self.colors}
     }
 
     fn draw_quad(top_left: Vertex, top_right: Vertex, bottom_right: Vertex, bottom_left: Vertex,
@@ -80,43 +245,267 @@ impl RubikDrawer
         target.draw(&shape_vb, &indices, program, &uniforms, &Default::default()).unwrap();
     }
 
-    /// A wrapper around [`draw_quad`].
-    /// 
-    /// [`draw_quad`]: fn.draw_quad.html
-    fn draw_square(grid_index: GridIndex, color: rubiks::Color, target: &mut Frame,
-        display: &Display, program: &Program)
+    /// Gives the top left and bottom right corners of the sticker at `grid_index` with `gap` (see [`with_gap`]) around it.
+    ///
+    /// [`with_gap`]: struct.RubikDrawer.html#method.with_gap
+    fn sticker_corners(grid_index: GridIndex, gap: f32) -> (Vertex, Vertex)
     {
         // Note, the glium draw space is from -1 to 1, how it should be
 
         let block_width = 2.0 / grid_index.cols as f32;
         let block_height = 2.0 / grid_index.rows as f32;
 
-        let spacer_size = if grid_index.cols > 10 * 4 { 9.0 } else { 50.0 };
-
         let top_left = Vertex { position: [ 
-                (grid_index.index.1 as f32 * block_width) - 1.0 + block_width / spacer_size, // last bit is a spacer
-                -1.0 * ((grid_index.index.0 as f32 * block_height) - 1.0 + block_height / spacer_size) ]}; // TODO: are these flipped ?
+                (grid_index.index.1 as f32 * block_width) - 1.0 + block_width * gap, // last bit is a spacer
+                -1.0 * ((grid_index.index.0 as f32 * block_height) - 1.0 + block_height * gap) ]}; // TODO: are these flipped ?
         let bottom_right = Vertex { position: [ 
-                ((grid_index.index.1+1) as f32 * block_width) - 1.0 - block_width / spacer_size,
-                -1.0 * (((grid_index.index.0+1) as f32 * block_height) - 1.0 - block_height / spacer_size) ]}; // are these flipped ?;
+                ((grid_index.index.1+1) as f32 * block_width) - 1.0 - block_width * gap,
+                -1.0 * (((grid_index.index.0+1) as f32 * block_height) - 1.0 - block_height * gap) ]}; // are these flipped ?;
+
+        (top_left, bottom_right)
+    }
+
+    /// A wrapper around [`draw_quad`].
+    /// 
+    /// [`draw_quad`]: fn.draw_quad.html
+    fn draw_square(corners: [Vertex; 4], color: rubiks::Color, target: &mut Frame, display: &Display, program: &Program)
+    {
+        let [top_left, top_right, bottom_right, bottom_left] = corners;
+
+        Self::draw_quad(top_left, top_right, bottom_right, bottom_left, Self::color_rgb(color), target, display, program)
+    }
+
+    /// Same as [`sticker_corners`] but gives all 4 corners, top left, top right, bottom right and bottom left.
+    ///
+    /// [`sticker_corners`]: struct.RubikDrawer.html#method.sticker_corners
+    fn square_corners(grid_index: GridIndex, gap: f32) -> [Vertex; 4]
+    {
+        let (top_left, bottom_right) = Self::sticker_corners(grid_index, gap);
 
         let top_right = Vertex { position: [ bottom_right.position[0],  top_left.position[1]] };
         let bottom_left = Vertex { position: [ top_left.position[0], bottom_right.position[1]] };
 
-        let color_rgb = match color
+        [top_left, top_right, bottom_right, bottom_left]
+    }
+
+    /// Where the sticker at index `sticker` (as in [`RubiksCubeState::data_at`]) of a nxnxn cube is in the net.
+    ///
+    /// [`RubiksCubeState::data_at`]: ../rubiks/struct.RubiksCubeState.html#method.data_at
+    fn net_grid_index(n: usize, sticker: usize) -> GridIndex
+    {
+        let (face, i, j) = (sticker / (n * n), (sticker % (n * n)) / n, sticker % n);
+        // ULFRBD, laid out like the `Debug` print
+        let (row, col) = match face
         {
-            rubiks::Color::White => (1.0, 1.0, 1.0),
-            rubiks::Color::Green => (0.0, 1.0, 0.0),
-            rubiks::Color::Red => (1.0, 0.0, 0.0),
-            rubiks::Color::Blue => (0.0, 0.0, 1.0),
-            rubiks::Color::Orange => (1.0, 0.5, 0.0),
-            rubiks::Color::Yellow => (1.0, 1.0, 0.0)
+            0 => (0, n),
+            1 => (n, 0),
+            2 => (n, n),
+            3 => (n, 2 * n),
+            4 => (n, 3 * n),
+            _ => (2 * n, n),
         };
 
-        Self::draw_quad(top_left, top_right, bottom_right, bottom_left, color_rgb, target, display, program)
+        GridIndex { cols: 4 * n, rows: 3 * n, index: (row + i, col + j) }
+    }
+
+    /// Gives the corners of the sticker moving from index `from` to `to` in the net when it is `t` (0 to 1) of the way
+    /// through the turn. Stickers that stay on their face rotate about the middle of the face, the rest slide
+    /// straight to where they go. At `t = 1` they are right on the sticker at `to`.
+    fn turning_corners(n: usize, from: usize, to: usize, t: f32, gap: f32) -> [Vertex; 4]
+    {
+        let start = Self::square_corners(Self::net_grid_index(n, from), gap);
+        let end = Self::square_corners(Self::net_grid_index(n, to), gap);
+
+        if from / (n * n) != to / (n * n)
+        {
+            let mut corners = start;
+            for (corner, end) in corners.iter_mut().zip(end.iter())
+            {
+                corner.position[0] += (end.position[0] - corner.position[0]) * t;
+                corner.position[1] += (end.position[1] - corner.position[1]) * t;
+            }
+            return corners;
+        }
+
+        // the net isn't square in the draw space, but each block of the grid is 1 by 1 so we rotate there
+        let (scale_x, scale_y) = (2.0 * n as f32, 1.5 * n as f32);
+        let face_start = (from / (n * n)) * n * n;
+        let (face_top_left, _) = Self::sticker_corners(Self::net_grid_index(n, face_start), 0.0);
+        let (_, face_bottom_right) = Self::sticker_corners(Self::net_grid_index(n, face_start + n * n - 1), 0.0);
+        let center = [(face_top_left.position[0] + face_bottom_right.position[0]) / 2.0,
+                      (face_top_left.position[1] + face_bottom_right.position[1]) / 2.0];
+        let to_grid = |v: Vertex| ((v.position[0] - center[0]) * scale_x, (v.position[1] - center[1]) * scale_y);
+        let middle = |corners: &[Vertex; 4]| to_grid(Vertex { position: [
+                (corners[0].position[0] + corners[2].position[0]) / 2.0,
+                (corners[0].position[1] + corners[2].position[1]) / 2.0] });
+
+        let ((x0, y0), (x1, y1)) = (middle(&start), middle(&end));
+        let mut angle = y1.atan2(x1) - y0.atan2(x0);
+        if angle > std::f32::consts::PI { angle -= 2.0 * std::f32::consts::PI; }
+        if angle < -std::f32::consts::PI { angle += 2.0 * std::f32::consts::PI; }
+        let (sin, cos) = (angle * t).sin_cos();
+
+        let mut corners = start;
+        for corner in corners.iter_mut()
+        {
+            let (x, y) = to_grid(*corner);
+            corner.position = [center[0] + (x * cos - y * sin) / scale_x, center[1] + (x * sin + y * cos) / scale_y];
+        }
+        corners
+    }
+
+    /// Gives the corners and color of every sticker in the net, in the order of [`RubiksCubeState::data_at`]. If
+    /// `turning` is `Some((turn, t))` then the stickers `turn` moves are drawn `t` (0 to 1) of the way there, see
+    /// [`Turn::affected_stickers`].
+    ///
+    /// [`RubiksCubeState::data_at`]: ../rubiks/struct.RubiksCubeState.html#method.data_at
+    /// [`Turn::affected_stickers`]: ../rubiks/enum.Turn.html#method.affected_stickers
+    fn net_stickers(cube_state: &rubiks::RubiksCubeState, turning: Option<(rubiks::Turn, f32)>, gap: f32) -> Vec<([Vertex; 4], rubiks::Color)>
+    {
+        let n = cube_state.size();
+        let mut stickers: Vec<_> = (0..6 * n * n)
+            .map(|i| (Self::square_corners(Self::net_grid_index(n, i), gap), cube_state.data_at(i)))
+            .collect();
+
+        if let Some((turn, t)) = turning
+        {
+            for (from, to) in turn.affected_stickers()
+            {
+                stickers[from].0 = Self::turning_corners(n, from, to, t, gap);
+            }
+        }
+
+        stickers
+    }
+
+    /// The color as [`draw_quad`] takes it, see [`Color::to_rgb`].
+    ///
+    /// [`draw_quad`]: struct.RubikDrawer.html#method.draw_quad
+    /// [`Color::to_rgb`]: ../rubiks/enum.Color.html#method.to_rgb
+    fn color_rgb(color: rubiks::Color) -> (f32, f32, f32)
+    {
+        let [r, g, b] = color.to_rgb();
+        (r, g, b)
+    }
+
+    /// Gives the top left, top right, bottom right and bottom left corners of the part of `face` (0 is up, 1 is front
+    /// and 2 is right, as from [`visible_faces`]) from `(x0, y0)` to `(x1, y1)`. These go from 0 to 1, left to right
+    /// and top to bottom, the same as the face in the net. Each face is a parallelogram and the corner the 3 faces share
+    /// is in the middle of the window.
+    ///
+    /// [`visible_faces`]: ../rubiks/struct.RubiksCubeState.html#method.visible_faces
+    fn isometric_corners(face: usize, (x0, y0): (f32, f32), (x1, y1): (f32, f32)) -> [Vertex; 4]
+    {
+        let size = 0.9;
+        let (cos30, sin30) = (size * 0.75f32.sqrt(), size * 0.5);
+
+        // where the top left of the face is and which way its rows and columns go
+        let (origin, x_dir, y_dir) = match face
+        {
+            0 => ([0.0, size], [cos30, -sin30], [-cos30, -sin30]),
+            1 => ([-cos30, sin30], [cos30, -sin30], [0.0, -size]),
+            _ => ([0.0, 0.0], [cos30, sin30], [0.0, -size]),
+        };
+        let point = |x: f32, y: f32| Vertex { position: [
+                origin[0] + x * x_dir[0] + y * y_dir[0],
+                origin[1] + x * x_dir[1] + y * y_dir[1] ]};
+
+        [point(x0, y0), point(x1, y0), point(x1, y1), point(x0, y1)]
     }
 
-    fn draw_face(grid_index_top_left: GridIndex, grid_index_top_right: GridIndex, 
+    /// Draws the cube onto `target` in the [`View::Isometric`] view.
+    ///
+    /// [`View::Isometric`]: enum.View.html#variant.Isometric
+    fn draw_cube_isometric(cube_state: &rubiks::RubiksCubeState, gap: f32, face_color: (f32, f32, f32), target: &mut Frame,
+        display: &Display, program: &Program)
+    {
+        let n = cube_state.size();
+        let faces = cube_state.visible_faces(rubiks::Face::Up, rubiks::Face::Front);
+        for (face, grid) in faces.iter().enumerate()
+        {
+            let [top_left, top_right, bottom_right, bottom_left] = Self::isometric_corners(face, (0.0, 0.0), (1.0, 1.0));
+            Self::draw_quad(top_left, top_right, bottom_right, bottom_left, face_color, target, display, program);
+
+            for (i, row) in grid.iter().enumerate()
+            {
+                for (j, &color) in row.iter().enumerate()
+                {
+                    let [top_left, top_right, bottom_right, bottom_left] = Self::isometric_corners(face,
+                        ((j as f32 + gap) / n as f32, (i as f32 + gap) / n as f32),
+                        ((j as f32 + 1.0 - gap) / n as f32, (i as f32 + 1.0 - gap) / n as f32));
+                    Self::draw_quad(top_left, top_right, bottom_right, bottom_left, Self::color_rgb(color), target, display, program);
+                }
+            }
+        }
+    }
+
+    /// The 4 sides of the warning border, as top left, top right, bottom right and bottom left corners, `width` wide
+    /// along the edges of the window.
+    fn border_quads(width: f32) -> [[Vertex; 4]; 4]
+    {
+        let quad = |x0: f32, y0: f32, x1: f32, y1: f32| [
+            Vertex { position: [x0, y0] }, Vertex { position: [x1, y0] },
+            Vertex { position: [x1, y1] }, Vertex { position: [x0, y1] }];
+
+        [quad(-1.0, 1.0, 1.0, 1.0 - width),
+         quad(-1.0, -1.0 + width, 1.0, -1.0),
+         quad(-1.0, 1.0, -1.0 + width, -1.0),
+         quad(1.0 - width, 1.0, 1.0, -1.0)]
+    }
+
+    /// The color the window is cleared to, as red, green, blue and alpha. An illegal state (see [`with_illegal_warning`])
+    /// gets the background tinted red, so the default white background becomes `(1.0, 0.85, 0.85)`.
+    ///
+    /// [`with_illegal_warning`]: struct.RubikDrawer.html#method.with_illegal_warning
+    fn clear_color(background: (f32, f32, f32), illegal: bool) -> (f32, f32, f32, f32)
+    {
+        let (r, g, b) = background;
+        if illegal
+        {
+            (r * 0.85 + 0.15, g * 0.85, b * 0.85, 1.0)
+        }
+        else
+        {
+            (r, g, b, 1.0)
+        }
+    }
+
+    /// Renders a single frame of the cube, with `turning` part way done in the [`View::Net`] view (see [`draw_cube`]).
+    /// If `warn_illegal` is set and the state isn't legal, the background is tinted and there is a warning border, see
+    /// [`with_illegal_warning`].
+    ///
+    /// [`View::Net`]: enum.View.html#variant.Net
+    /// [`draw_cube`]: struct.RubikDrawer.html#method.draw_cube
+    /// [`with_illegal_warning`]: struct.RubikDrawer.html#method.with_illegal_warning
+    fn draw(cube_state: &rubiks::RubiksCubeState, turning: Option<(rubiks::Turn, f32)>, settings: DrawSettings,
+        display: &Display, program: &Program)
+    {
+        let DrawSettings{view, gap, warn_illegal, colors} = settings;
+        let illegal = warn_illegal && !cube_state.is_legal();
+
+        let mut target = display.draw();
+        let (r, g, b, a) = Self::clear_color(colors.background, illegal);
+        target.clear_color(r, g, b, a);
+
+        match view
+        {
+            View::Net => Self::draw_cube(cube_state, turning, gap, colors.face, &mut target, display, program),
+            View::Isometric => Self::draw_cube_isometric(cube_state, gap, colors.face, &mut target, display, program),
+        }
+
+        if illegal
+        {
+            // magenta isn't a sticker color so the border can't be mistaken for part of the cube
+            for [top_left, top_right, bottom_right, bottom_left] in Self::border_quads(0.03).iter().copied()
+            {
+                Self::draw_quad(top_left, top_right, bottom_right, bottom_left, (1.0, 0.0, 1.0), &mut target, display, program);
+            }
+        }
+
+        let _ = target.finish();
+    }
+
+    fn draw_face(grid_index_top_left: GridIndex, grid_index_top_right: GridIndex, color_rgb: (f32, f32, f32),
         target: &mut Frame, display: &Display, program: &Program)
     {
         // Note, the glium draw space is from -1 to 1, how it should be
@@ -134,81 +523,95 @@ impl RubikDrawer
         let top_right = Vertex { position: [ bottom_right.position[0],  top_left.position[1]] };
         let bottom_left = Vertex { position: [ top_left.position[0], bottom_right.position[1]] };
 
-        let color_rgb = (0.5, 0.5, 0.5);
-
         Self::draw_quad(top_left, top_right, bottom_right, bottom_left, color_rgb, target, display, program)
     }
 
-    /// Renders a single frame for the game.
-    fn draw_cube(cube_state: &rubiks::RubiksCubeState, display: &Display, program: &Program)
+    /// Draws the cube onto `target` in the [`View::Net`] view, with the layer of `turning` part way turned if there is
+    /// one, see [`net_stickers`].
+    ///
+    /// [`View::Net`]: enum.View.html#variant.Net
+    /// [`net_stickers`]: struct.RubikDrawer.html#method.net_stickers
+    fn draw_cube(cube_state: &rubiks::RubiksCubeState, turning: Option<(rubiks::Turn, f32)>, gap: f32, face_color: (f32, f32, f32),
+        target: &mut Frame, display: &Display, program: &Program)
     {
-        let mut target = display.draw();
-        target.clear_color(1.0,1.0,1.0, 1.0);  // gray
-
         let cols = 4 * cube_state.size();
         let rows = 3 * cube_state.size();
         let n = cube_state.size();
 
-        // UP
-        Self::draw_face(GridIndex { cols, rows, index: (0,n) }, GridIndex { cols, rows, index: (n-1,2*n-1) }, &mut target, display, program);
-        for i in 0..n
-        {
-            for j in 0..n
-            {
-                let grid_index = GridIndex { cols, rows, index: (i,j+n) };
+        // UP, LFRB and Down
+        Self::draw_face(GridIndex { cols, rows, index: (0,n) }, GridIndex { cols, rows, index: (n-1,2*n-1) }, face_color, target, display, program);
+        Self::draw_face(GridIndex { cols, rows, index: (n,0) }, GridIndex { cols, rows, index: (2*n-1,n-1) }, face_color, target, display, program);
+        Self::draw_face(GridIndex { cols, rows, index: (n,n) }, GridIndex { cols, rows, index: (2*n-1,2*n-1) }, face_color, target, display, program);
+        Self::draw_face(GridIndex { cols, rows, index: (n,2*n) }, GridIndex { cols, rows, index: (2*n-1,3*n-1) }, face_color, target, display, program);
+        Self::draw_face(GridIndex { cols, rows, index: (n,3*n) }, GridIndex { cols, rows, index: (2*n-1,4*n-1) }, face_color, target, display, program);
+        Self::draw_face(GridIndex { cols, rows, index: (2*n,n) }, GridIndex { cols, rows, index: (3*n-1,2*n-1) }, face_color, target, display, program);
 
-                Self::draw_square(grid_index, cube_state.data_at(n*i + j), &mut target, display, program);
-            }
+        for (corners, color) in Self::net_stickers(cube_state, turning, gap)
+        {
+            Self::draw_square(corners, color, target, display, program);
         }
+    }
 
-        // LFRB
-        Self::draw_face(GridIndex { cols, rows, index: (n,0) }, GridIndex { cols, rows, index: (2*n-1,n-1) }, &mut target, display, program);
-        Self::draw_face(GridIndex { cols, rows, index: (n,n) }, GridIndex { cols, rows, index: (2*n-1,2*n-1) }, &mut target, display, program);
-        Self::draw_face(GridIndex { cols, rows, index: (n,2*n) }, GridIndex { cols, rows, index: (2*n-1,3*n-1) }, &mut target, display, program);
-        Self::draw_face(GridIndex { cols, rows, index: (n,3*n) }, GridIndex { cols, rows, index: (2*n-1,4*n-1) }, &mut target, display, program);
-        for i in 0..n
+    /// Moves the animation from [`with_animation`] up to `now`. Turns whose time is up are done on `cube_state` and
+    /// taken off `turns`, and `turn_start` moves up to when the next one started. Gives the turn being made and how far
+    /// along it is, from 0 to 1, or `None` once they are all done.
+    ///
+    /// [`with_animation`]: struct.RubikDrawer.html#method.with_animation
+    fn animation_frame(cube_state: &mut rubiks::RubiksCubeState, turns: &mut VecDeque<rubiks::Turn>,
+        turn_start: &mut time::Instant, millis_per_turn: u64, now: time::Instant) -> Option<(rubiks::Turn, f32)>
+    {
+        let turn_time = time::Duration::from_millis(millis_per_turn);
+        while let Some(&turn) = turns.front()
         {
-            // Left
-            for j in 0..n
-            {
-                let grid_index = GridIndex { cols, rows, index: (i+n,j) };
-                Self::draw_square(grid_index, cube_state.data_at(n*n + n*i + j), &mut target, display, program);
-            }
-            
-            // Front
-            for j in 0..n
-            {
-                let grid_index = GridIndex { cols, rows, index: (i+n,j+n) };
-                Self::draw_square(grid_index, cube_state.data_at(n*n*2 + n*i + j), &mut target, display, program);
-            }
-            
-            // Right
-            for j in 0..n
+            let elapsed = now.saturating_duration_since(*turn_start);
+            if elapsed < turn_time
             {
-                let grid_index = GridIndex { cols, rows, index: (i+n,j+2*n) };
-                Self::draw_square(grid_index, cube_state.data_at(n*n*3 + n*i + j), &mut target, display, program);
-            }
-            
-            // Back
-            for j in 0..n
-            {
-                let grid_index = GridIndex { cols, rows, index: (i+n,j+3*n) };
-                Self::draw_square(grid_index, cube_state.data_at(n*n*4 + n*i + j), &mut target, display, program);
+                return Some((turn, elapsed.as_secs_f32() / turn_time.as_secs_f32()));
             }
+
+            cube_state.turn(turn);
+            turns.pop_front();
+            *turn_start += turn_time;
         }
 
-        // Down
-        Self::draw_face(GridIndex { cols, rows, index: (2*n,n) }, GridIndex { cols, rows, index: (3*n-1,2*n-1) }, &mut target, display, program);
-        for i in 0..n
+        None
+    }
+
+    /// The keys x, y and z turn the whole cube in the window (see [`Move::rotation`]) so you can see it from other sides.
+    /// The cube is the same, only how it is held changes.
+    ///
+    /// [`Move::rotation`]: ../rubiks/struct.Move.html#method.rotation
+    fn rotation_key(c: char) -> Option<rubiks::Axis>
+    {
+        match c
         {
-            for j in 0..n
-            {
-                let grid_index = GridIndex { cols, rows, index: (i+2*n,j+n) };
-                Self::draw_square(grid_index, cube_state.data_at(n*n*5 + n*i + j), &mut target, display, program);
-            }
+            'x' | 'X' => Some(rubiks::Axis::X),
+            'y' | 'Y' => Some(rubiks::Axis::Y),
+            'z' | 'Z' => Some(rubiks::Axis::Z),
+            _ => None,
         }
+    }
 
-        let _ = target.finish();
+    /// Same as [`show`] but in the [`View::Isometric`] view.
+    ///
+    /// [`show`]: struct.RubikDrawer.html#method.show
+    /// [`View::Isometric`]: enum.View.html#variant.Isometric
+    #[allow(dead_code)]
+    #[cfg(target_family = "unix")]
+    pub fn show_3d(&self)
+    {
+        RubikDrawer{view: View::Isometric, ..self.clone()}.show()
+    }
+
+    /// Same as [`show`] but in the [`View::Isometric`] view.
+    ///
+    /// [`show`]: struct.RubikDrawer.html#method.show
+    /// [`View::Isometric`]: enum.View.html#variant.Isometric
+    #[allow(dead_code)]
+    #[cfg(target_family = "windows")]
+    pub fn show_3d(&self) -> !
+    {
+        RubikDrawer{view: View::Isometric, ..self.clone()}.show()
     }
 
     /// This is hacky, there must be a better way then to fork the process.
@@ -252,9 +655,15 @@ impl RubikDrawer
 
                 let program = glium::Program::from_source(&display, vertex_shader_src, fragment_shader_src, None).unwrap();
 
-                let cube_state = self.state.clone();
+                let mut cube_state = self.state.clone();
+                let settings = self.draw_settings();
+                let smooth = self.smooth;
+                let millis_per_turn = self.millis_per_turn;
+                let mut turns: VecDeque<rubiks::Turn> = self.turns.iter().copied().collect();
+                let mut turning = None;
+                let mut turn_start = time::Instant::now();
 
-                Self::draw_cube(&cube_state, &display, &program);
+                Self::draw(&cube_state, turning, settings, &display, &program);
 
                 event_loop.run(move |event, _, control_flow|
                 {
@@ -273,13 +682,31 @@ impl RubikDrawer
                                 *control_flow = glutin::event_loop::ControlFlow::Exit;
                                 return;
                             },
-                            glutin::event::WindowEvent::Resized(_) => Self::draw_cube(&cube_state, &display, &program),
+                            glutin::event::WindowEvent::Resized(_) => Self::draw(&cube_state, turning, settings, &display, &program),
+                            glutin::event::WindowEvent::ReceivedCharacter(c) =>
+                            {
+                                if let Some(axis) = Self::rotation_key(c)
+                                {
+                                    cube_state.rotate_cube(axis);
+                                    Self::draw(&cube_state, turning, settings, &display, &program);
+                                }
+                            },
                             _ => return,
                         },
+                        glutin::event::Event::MainEventsCleared if !turns.is_empty() =>
+                        {
+                            // while turning we redraw every frame, not only when something happens
+                            let now = time::Instant::now();
+                            let frame = Self::animation_frame(&mut cube_state, &mut turns, &mut turn_start, millis_per_turn, now);
+                            turning = if smooth { frame } else { None };
+                            Self::draw(&cube_state, turning, settings, &display, &program);
+
+                            *control_flow = glutin::event_loop::ControlFlow::WaitUntil(now + time::Duration::from_nanos(16_666_667));
+                        },
                         _ => (),
                     }
                     
-                    //Self::draw_cube(&cube_state, &display, &program);  // TODO: do we need the loop
+                    //Self::draw(&cube_state, settings, &display, &program);  // TODO: do we need the loop
                 });
             },
             Err(_) => println!("Fork failed"),
@@ -318,9 +745,15 @@ impl RubikDrawer
 
         let program = glium::Program::from_source(&display, vertex_shader_src, fragment_shader_src, None).unwrap();
 
-        let cube_state = self.state.clone();
+        let mut cube_state = self.state.clone();
+        let settings = self.draw_settings();
+        let smooth = self.smooth;
+        let millis_per_turn = self.millis_per_turn;
+        let mut turns: VecDeque<rubiks::Turn> = self.turns.iter().copied().collect();
+        let mut turning = None;
+        let mut turn_start = time::Instant::now();
 
-        Self::draw_cube(&cube_state, &display, &program);
+        Self::draw(&cube_state, turning, settings, &display, &program);
 
         event_loop.run(move |event, _, control_flow|
         {
@@ -339,13 +772,220 @@ impl RubikDrawer
                         *control_flow = glutin::event_loop::ControlFlow::Exit;
                         return;
                     },
-                    glutin::event::WindowEvent::Resized(_) => Self::draw_cube(&cube_state, &display, &program),
+                    glutin::event::WindowEvent::Resized(_) => Self::draw(&cube_state, turning, settings, &display, &program),
+                    glutin::event::WindowEvent::ReceivedCharacter(c) =>
+                    {
+                        if let Some(axis) = Self::rotation_key(c)
+                        {
+                            cube_state.rotate_cube(axis);
+                            Self::draw(&cube_state, turning, settings, &display, &program);
+                        }
+                    },
                     _ => return,
                 },
+                glutin::event::Event::MainEventsCleared if !turns.is_empty() =>
+                {
+                    // while turning we redraw every frame, not only when something happens
+                    let now = time::Instant::now();
+                    let frame = Self::animation_frame(&mut cube_state, &mut turns, &mut turn_start, millis_per_turn, now);
+                    turning = if smooth { frame } else { None };
+                    Self::draw(&cube_state, turning, settings, &display, &program);
+
+                    *control_flow = glutin::event_loop::ControlFlow::WaitUntil(now + time::Duration::from_nanos(16_666_667));
+                },
                 _ => (),
             }
             
-            //Self::draw_cube(&cube_state, &display, &program);  // TODO: do we need the loop
+            //Self::draw(&cube_state, settings, &display, &program);  // TODO: do we need the loop
         })
     }
 }
+
+#[test]
+fn test_sticker_gap()
+{
+    for n in 2..20
+    {
+        let (cols, rows) = (4 * n, 3 * n);
+
+        for &gap in [0.0, RubikDrawer::default_gap(n), 0.3].iter()
+        {
+            for i in 0..rows
+            {
+                for j in 0..cols
+                {
+                    let (top_left, bottom_right) = RubikDrawer::sticker_corners(GridIndex { cols, rows, index: (i, j) }, gap);
+                    for &v in top_left.position.iter().chain(bottom_right.position.iter())
+                    {
+                        assert!((-1.0 - 1e-5..=1.0 + 1e-5).contains(&v));
+                    }
+                    assert!(top_left.position[0] < bottom_right.position[0]);
+                    assert!(top_left.position[1] > bottom_right.position[1]);
+                }
+            }
+        }
+
+        // the gap grows with the size, but not past 0.12
+        assert!(RubikDrawer::default_gap(n) <= RubikDrawer::default_gap(n + 1));
+        assert!(RubikDrawer::default_gap(n) >= 0.02 && RubikDrawer::default_gap(n) <= 0.12);
+    }
+}
+
+#[test]
+fn test_isometric_corners()
+{
+    let close = |a: Vertex, b: Vertex| (a.position[0] - b.position[0]).abs() < 1e-5 && (a.position[1] - b.position[1]).abs() < 1e-5;
+
+    for face in 0..3
+    {
+        let corners = RubikDrawer::isometric_corners(face, (0.0, 0.0), (1.0, 1.0));
+        for v in corners.iter()
+        {
+            assert!(v.position[0].abs() <= 1.0 && v.position[1].abs() <= 1.0);
+        }
+
+        // a parallelogram
+        assert!(close(Vertex { position: [corners[1].position[0] - corners[0].position[0], corners[1].position[1] - corners[0].position[1]] },
+                      Vertex { position: [corners[2].position[0] - corners[3].position[0], corners[2].position[1] - corners[3].position[1]] }));
+    }
+
+    let up = RubikDrawer::isometric_corners(0, (0.0, 0.0), (1.0, 1.0));
+    let front = RubikDrawer::isometric_corners(1, (0.0, 0.0), (1.0, 1.0));
+    let right = RubikDrawer::isometric_corners(2, (0.0, 0.0), (1.0, 1.0));
+
+    // the corner all 3 share is in the middle
+    assert!(close(up[2], Vertex { position: [0.0, 0.0] }));
+    assert!(close(front[1], up[2]) && close(right[0], up[2]));
+
+    // up and front share the top front edge, up and right share the top right edge and front and right share the front right edge
+    assert!(close(up[3], front[0]));
+    assert!(close(up[1], right[1]));
+    assert!(close(front[2], right[3]));
+}
+
+#[test]
+fn test_from_state_checked()
+{
+    assert!(RubikDrawer::from_state_checked(rubiks::RubiksCubeState::std_solved_nxnxn(3)).is_ok());
+    assert!(!RubikDrawer::from_state(rubiks::RubiksCubeState::std_solved_nxnxn(3)).warn_illegal);
+
+    // the URF corner twisted in place
+    let solved = rubiks::RubiksCubeState::std_solved_nxnxn(3);
+    let mut stickers: Vec<char> = (0..54).map(|i| solved.data_at(i).as_char()).collect();
+    let (urf_u, urf_r, urf_f) = (stickers[8], stickers[27], stickers[20]);
+    stickers[8] = urf_r;
+    stickers[27] = urf_f;
+    stickers[20] = urf_u;
+    let twisted = rubiks::RubiksCubeState::from_state_string(&stickers.into_iter().collect()).unwrap();
+
+    let drawer = RubikDrawer::from_state_checked(twisted).err().unwrap();
+    assert!(drawer.warn_illegal);
+
+    let width = 0.03;
+    for side in RubikDrawer::border_quads(width).iter()
+    {
+        for v in side.iter()
+        {
+            assert!(v.position[0].abs() <= 1.0 && v.position[1].abs() <= 1.0);
+        }
+        // each side runs the length of the window and is `width` across
+        let (w, h) = ((side[1].position[0] - side[0].position[0]).abs(), (side[0].position[1] - side[3].position[1]).abs());
+        assert!((w.min(h) - width).abs() < 1e-5 && (w.max(h) - 2.0).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn test_turn_animation_frames()
+{
+    let same_corners = |a: &[Vertex; 4], b: &[Vertex; 4]| a.iter().all(|u| b.iter().any(|v|
+        (u.position[0] - v.position[0]).abs() < 1e-4 && (u.position[1] - v.position[1]).abs() < 1e-4));
+
+    for n in 2..6
+    {
+        let gap = RubikDrawer::default_gap(n);
+        let mut state = rubiks::RubiksCubeState::std_solved_nxnxn(n);
+        state.do_move(&rubiks::Move::rnd_move(n, 20));
+
+        for turn in rubiks::Move::rnd_move(n, 10)
+        {
+            let after = state.clone().with_turn(turn);
+
+            // the first frame is the state before and the last frame is the state after
+            let first = RubikDrawer::net_stickers(&state, Some((turn, 0.0)), gap);
+            for (frame, still) in first.iter().zip(RubikDrawer::net_stickers(&state, None, gap).iter())
+            {
+                assert!(same_corners(&frame.0, &still.0) && frame.1 == still.1);
+            }
+
+            let last = RubikDrawer::net_stickers(&state, Some((turn, 1.0)), gap);
+            for (corners, color) in RubikDrawer::net_stickers(&after, None, gap)
+            {
+                assert!(last.iter().any(|frame| same_corners(&frame.0, &corners) && frame.1 == color));
+            }
+
+            // part way the turning stickers are somewhere else
+            let middle = RubikDrawer::net_stickers(&state, Some((turn, 0.5)), gap);
+            for (from, _) in turn.affected_stickers()
+            {
+                assert!(!same_corners(&middle[from].0, &first[from].0));
+            }
+
+            state = after;
+        }
+    }
+}
+
+#[test]
+fn test_animation_frame()
+{
+    let n = 3;
+    let the_move = rubiks::Move::from_notation("R U F'", n).unwrap();
+    let mut cube_state = rubiks::RubiksCubeState::std_solved_nxnxn(n);
+    let mut turns: VecDeque<rubiks::Turn> = the_move.turns.iter().copied().collect();
+    let start = time::Instant::now();
+    let mut turn_start = start;
+
+    let frame = RubikDrawer::animation_frame(&mut cube_state, &mut turns, &mut turn_start, 100, start + time::Duration::from_millis(50));
+    assert_eq!(frame.map(|(turn, _)| turn), Some(the_move.turns[0]));
+    assert!((frame.unwrap().1 - 0.5).abs() < 1e-5);
+    assert!(cube_state.is_solved());
+
+    let frame = RubikDrawer::animation_frame(&mut cube_state, &mut turns, &mut turn_start, 100, start + time::Duration::from_millis(225));
+    assert_eq!(frame.map(|(turn, _)| turn), Some(the_move.turns[2]));
+    assert!((frame.unwrap().1 - 0.25).abs() < 1e-5);
+    assert_eq!(turns.len(), 1);
+
+    assert!(RubikDrawer::animation_frame(&mut cube_state, &mut turns, &mut turn_start, 100, start + time::Duration::from_millis(300)).is_none());
+    assert!(turns.is_empty());
+    assert_eq!(cube_state, rubiks::RubiksCubeState::std_solved_nxnxn(n).with_move(&the_move));
+}
+
+#[test]
+fn test_sticker_colors()
+{
+    use rubiks::Color;
+
+    let expected = [(Color::White, (1.0, 1.0, 1.0), [255, 255, 255]), (Color::Green, (0.0, 1.0, 0.0), [0, 255, 0]),
+                    (Color::Red, (1.0, 0.0, 0.0), [255, 0, 0]), (Color::Blue, (0.0, 0.0, 1.0), [0, 0, 255]),
+                    (Color::Orange, (1.0, 0.5, 0.0), [255, 128, 0]), (Color::Yellow, (1.0, 1.0, 0.0), [255, 255, 0])];
+    for &(color, rgb, rgb8) in expected.iter()
+    {
+        assert_eq!(RubikDrawer::color_rgb(color), rgb);
+        assert_eq!(color.to_rgb8(), rgb8);
+    }
+}
+
+#[test]
+fn test_colors()
+{
+    let drawer = RubikDrawer::from_state(rubiks::RubiksCubeState::std_solved_nxnxn(3));
+    assert_eq!(drawer.colors, Colors{face: (0.5, 0.5, 0.5), background: (1.0, 1.0, 1.0)});
+    assert_eq!(RubikDrawer::clear_color(drawer.colors.background, false), (1.0, 1.0, 1.0, 1.0));
+    assert_eq!(RubikDrawer::clear_color(drawer.colors.background, true), (1.0, 0.85, 0.85, 1.0));
+
+    let black = drawer.with_background((0.0, 0.0, 0.0)).with_face_color((0.2, 0.2, 0.2));
+    assert_eq!(black.colors, Colors{face: (0.2, 0.2, 0.2), background: (0.0, 0.0, 0.0)});
+    assert_eq!(RubikDrawer::clear_color(black.colors.background, false), (0.0, 0.0, 0.0, 1.0));
+    let (r, g, b, _) = RubikDrawer::clear_color(black.colors.background, true);
+    assert!(r > 0.0 && g == 0.0 && b == 0.0);
+}
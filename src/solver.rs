@@ -1,13 +1,64 @@
 use std::collections::VecDeque;
 use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::collections::BTreeMap;
+use std::collections::HashSet;
 use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use rand::SeedableRng;
 
 use super::rubiks;
 
+/// The kinds of tables a [`HeuristicsTables`] can hold.
+///
+/// [`HeuristicsTables`]: struct.HeuristicsTables.html
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TableKind
+{
+    /// The distance to solve the corners, from the 2x2x2 cube. Works for every cube size.
+    Corners,
+    /// The distance to solve the edges. This can't be built yet.
+    Edges,
+}
+
+/// How turns are counted. In the quarter turn metric (QTM) a half turn is 2 turns, in the half turn metric (HTM) it is 1.
+#[allow(dead_code)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Metric
+{
+    Quarter,
+    Half,
+}
+
+/// The number of states of the 2x2x2 up to how it is held, so the number of entries in the corner table.
+pub const NUM_2X2X2_STATES: usize = 3674160;
+
+/// The most states the bfs that builds the corner table has in its queue at once, found by building it. Each state is
+/// queued once, when it is first found, so the queue only has the rest of one depth and the start of the next.
+const MAX_CORNER_BFS_QUEUE: usize = 1499077;
+
+/// God's number for the nxnxn cube, the most turns any state needs to be solved, or `None` if it isn't known.
+/// It is known for the 2x2x2 (14 QTM, 11 HTM) and the 3x3x3 (26 QTM, 20 HTM). The 1x1x1 has no turns so it is 0.
+pub fn gods_number(n: usize, metric: Metric) -> Option<usize>
+{
+    match (n, metric)
+    {
+        (1, _) => Some(0),
+        (2, Metric::Quarter) => Some(14),
+        (2, Metric::Half) => Some(11),
+        (3, Metric::Quarter) => Some(26),
+        (3, Metric::Half) => Some(20),
+        _ => None,
+    }
+}
+
 #[derive(Default)]
 pub struct HeuristicsTables
 {
-    corners: Option<HashMap<rubiks::RubiksCubeState, u8>>,
+    corners: Option<HashMap<rubiks::CanonicalState, u8>>,
 }
 
 impl HeuristicsTables
@@ -19,478 +70,2211 @@ impl HeuristicsTables
 
     pub fn calc_corner_heuristics_table(&mut self)
     {
-        let mut hash_table: HashMap<rubiks::RubiksCubeState, u8> = HashMap::with_capacity(4000000); // TODO: change size
+        self.calc_corner_heuristics_table_with_progress(usize::MAX, |_, _| {});
+    }
+
+    /// Same as [`calc_corner_heuristics_table`] but calls `progress` every `every` states with the current depth
+    /// of the bfs and the number of states found so far (out of 3674160). Use this to show how far along it is.
+    /// An `every` of 0 never calls `progress`.
+    ///
+    /// [`calc_corner_heuristics_table`]: struct.HeuristicsTables.html#method.calc_corner_heuristics_table
+    pub fn calc_corner_heuristics_table_with_progress<F>(&mut self, every: usize, mut progress: F)
+        where F: FnMut(u8, usize)
+    {
+        let mut hash_table: HashMap<rubiks::CanonicalState, u8> = HashMap::with_capacity(NUM_2X2X2_STATES);
         let mut num_pos = 0;
 
         let solv_state = rubiks::RubiksCubeState::std_solved_nxnxn(2);
+        let max_depth = gods_number(2, Metric::Quarter).unwrap() as u8;
 
-        let mut vq: VecDeque<(rubiks::RubiksCubeState, u8)> = VecDeque::with_capacity(3674160/2);
+        let mut vq: VecDeque<(rubiks::RubiksCubeState, u8)> = VecDeque::with_capacity(MAX_CORNER_BFS_QUEUE);
+        hash_table.insert(rubiks::CanonicalState::new(&solv_state), 0);
         vq.push_back((solv_state, 0));
+        let mut peak_queue = vq.len();
 
         while let Some((state, i)) = vq.pop_front()
         {
-            if hash_table.contains_key(&state) { continue; }
-
             // Note, the bottom left cubie is the same for all states
-            if i < 14
+            if i < max_depth
             {
                 for turn_type in state.all_turns().into_iter()
                     .filter(|t| matches!(t.into_axis_based(), rubiks::Turn::AxisBased{index, ..} if index > 0)) // remove negative index turns
                 {
-                    let mut new_state = state.clone();
-                    new_state.turn(turn_type);
-                    if ! hash_table.contains_key(&new_state)
+                    let new_state = state.turn_copy(turn_type);
+                    if let Entry::Vacant(entry) = hash_table.entry(rubiks::CanonicalState::new(&new_state))
                     {
-                        // already been found and in less turns
-                        vq.push_back((new_state, i+1))
+                        // not found yet, so this is the fewest turns to it
+                        entry.insert(i+1);
+                        vq.push_back((new_state, i+1));
                     }
                 }
+                peak_queue = peak_queue.max(vq.len());
             }
 
-            hash_table.insert(state, i);
             num_pos += 1;
+
+            if every != 0 && num_pos % every == 0
+            {
+                progress(i, num_pos);
+            }
         }
 
         self.corners = Some(hash_table);
-        assert_eq!(num_pos, 3674160);
+        assert_eq!(num_pos, NUM_2X2X2_STATES);
+        // the queue never grew past what it was given
+        assert!(peak_queue <= MAX_CORNER_BFS_QUEUE);
     }
 
+    /// The exact number of quarter turns needed to solve the 2x2x2 `state` (held any way), not just a lower bound.
+    /// Gives `None` if the corner table hasn't been built, `state` is not a 2x2x2 or it doesn't use the standard colors.
     #[allow(dead_code)]
-    pub fn calc_edge_heuristics_table(&mut self, edge_type: bool)
+    pub fn exact_2x2_distance(&self, state: &rubiks::RubiksCubeState) -> Option<u8>
     {
-        todo!()
-    }
-}
+        if state.size() != 2
+        {
+            return None;
+        }
 
-impl fmt::Debug for HeuristicsTables {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("HeuristicsTables")
-         .field("corners", &matches!(&self.corners, Some(_)))
-         .finish()
+        self.corners.as_ref().and_then(|corner_ht| corner_ht.get(&rubiks::CanonicalState::new(state)).copied())
     }
-}
-
-#[derive(Debug)]
-pub enum RubikSolveError
-{
-    Unsolveable,
-    BadInput,
-    NoHeuristicsTable,
-}
 
-// #[derive(Clone, Debug)]
-pub struct RubiksCubeSolver
-{
-    //state: rubiks::RubiksCubeState,
-    heuristic_table: Option<HeuristicsTables>,
-}
+    /// The corner table if it has been built. It has every 2x2x2 state up to how it is held (3674160 of them)
+    /// and the number of quarter turns to solve it, from 0 to 14.
+    #[allow(dead_code)]
+    pub fn corner_table(&self) -> Option<&HashMap<rubiks::CanonicalState, u8>>
+    {
+        self.corners.as_ref()
+    }
 
-impl RubiksCubeSolver
-{
-    pub fn new() -> Self
+    /// The number of quarter turns to solve the corners of `big_state`, a cube of any size held any way,
+    /// or `None` if there is no corner table or the state isn't in it (like when it doesn't use the standard colors).
+    /// The solvers use this for the corner heuristic, see [`RubiksCubeState::corners_2x2x2_normalized`].
+    ///
+    /// [`RubiksCubeState::corners_2x2x2_normalized`]: ../rubiks/struct.RubiksCubeState.html#method.corners_2x2x2_normalized
+    pub fn corner_distance(&self, big_state: &rubiks::RubiksCubeState) -> Option<u8>
     {
-        RubiksCubeSolver{heuristic_table: None}
+        self.corners.as_ref().and_then(|corner_ht| corner_ht.get(&rubiks::CanonicalState::corners_of(big_state)).copied())
     }
 
-    pub fn calc_new_heuristics_table(&mut self)
+    /// The kinds of tables that have been built.
+    #[allow(dead_code)]
+    pub fn kinds(&self) -> Vec<TableKind>
     {
-        let mut ht = HeuristicsTables::new();
-        ht.calc_corner_heuristics_table();
+        [TableKind::Corners, TableKind::Edges].iter().copied().filter(|&kind| self.has(kind)).collect()
+    }
 
-        self.heuristic_table = Some(ht);
+    /// Checks if the `kind` table has been built, the solvers check this before using a table.
+    pub fn has(&self, kind: TableKind) -> bool
+    {
+        match kind
+        {
+            TableKind::Corners => self.corners.is_some(),
+            TableKind::Edges => false,
+        }
     }
 
     #[allow(dead_code)]
-    pub fn add_heuristics_table(&mut self, heuristics_table: HeuristicsTables)
+    pub fn calc_edge_heuristics_table(&mut self, edge_type: bool)
     {
-        if let None = self.heuristic_table
+        todo!()
+    }
+}
+
+// The tables are far too big to print, so this only says what is in them. A full corner table has 3674160 entries
+// and a max distance of 14.
+impl fmt::Debug for HeuristicsTables {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("HeuristicsTables");
+        debug.field("corners", &matches!(&self.corners, Some(_)));
+        if let Some(corner_ht) = &self.corners
         {
-            self.heuristic_table = Some(heuristics_table);
+            debug.field("corner_entries", &corner_ht.len())
+                 .field("corner_max_distance", &corner_ht.values().max().copied().unwrap_or(0));
         }
+        debug.finish()
     }
+}
 
-    pub fn solver_2x2x2_with_heuristics_table(&self, rubiks_state: &rubiks::RubiksCubeState) -> Result<rubiks::Move, RubikSolveError>
+/// The search for [`RubiksCubeSolver::solutions_iter`]. This is an iterative deepening dfs with the stack kept here
+/// so it can stop after each solution and pick up where it left off.
+///
+/// [`RubiksCubeSolver::solutions_iter`]: struct.RubiksCubeSolver.html#method.solutions_iter
+struct SolutionsIter<'a>
+{
+    solver: &'a RubiksCubeSolver,
+    /// The start state with `path` applied.
+    state: rubiks::RubiksCubeState,
+    path: rubiks::Move,
+    /// For each depth in the path, the next turn to try from `all_turns`.
+    next_index: Vec<usize>,
+    all_turns: Vec<rubiks::Turn>,
+    /// Only solutions this long are given in this pass of the search.
+    depth_limit: usize,
+    k: usize,
+    started: bool,
+}
+
+impl<'a> Iterator for SolutionsIter<'a>
+{
+    type Item = rubiks::Move;
+
+    fn next(&mut self) -> Option<rubiks::Move>
     {
-        if rubiks_state.size() != 2 { return Err(RubikSolveError::BadInput); }
+        if !self.started
+        {
+            self.started = true;
+            if self.state.is_solved()
+            {
+                // every other solution would go through the solved cube
+                self.k = 0;
+                return Some(rubiks::Move::empty());
+            }
+        }
 
-        if let Some(heuristic_table) = &self.heuristic_table
+        loop
         {
-            if let Some(ref corner_ht) = &heuristic_table.corners
+            if self.solver.is_cancelled()
             {
-                let mut tmp_state = rubiks_state.clone();
-                tmp_state.rotate_to_normal_2x2x2();
-                if rubiks_state.is_solved()
+                return None;
+            }
+
+            if self.next_index.is_empty()
+            {
+                // done with this depth, go one deeper
+                self.depth_limit += 1;
+                if self.depth_limit > self.k
                 {
-                    return Ok(rubiks::Move::empty());
+                    return None;
                 }
-                else if let None = corner_ht.get(&tmp_state)
+                self.next_index.push(0);
+                continue;
+            }
+
+            let depth = self.path.turns.len();
+            let i = *self.next_index.last().unwrap();
+            if depth == self.depth_limit || i >= self.all_turns.len()
+            {
+                // back track
+                self.next_index.pop();
+                if let Some(turn) = self.path.turns.pop()
                 {
-                    return Err(RubikSolveError::Unsolveable);
+                    self.state.turn(turn.invert());
                 }
+                continue;
+            }
 
-                let v = corner_ht.get(&tmp_state).map(|v| *v as usize).unwrap();
+            *self.next_index.last_mut().unwrap() += 1;
+            let turn = self.all_turns[i];
+            if !self.path.is_next_turn_efficient(turn)
+            {
+                continue;
+            }
 
-                let mut this_state = rubiks_state.clone();
-                let mut this_move = rubiks::Move::empty();
+            self.state.turn(turn);
+            self.path.turns.push(turn);
 
-                let mut v_left = v;
-                for _ in 0..v
-                {
-                    let mut next_turn: Option<rubiks::Turn> = None;
-                    for turn_type in rubiks_state.all_turns()
-                    {
-                        let mut tmp_state = this_state.clone();
-                        tmp_state.turn(turn_type);
-                        tmp_state.rotate_to_normal_2x2x2();
-                        if let Some(new_v) = corner_ht.get(&tmp_state).map(|v| *v as usize)
-                        {
-                            if new_v < v_left 
-                            {
-                                next_turn = Some(turn_type);
-                                v_left = new_v;
-                                break;
-                            }
-                        }
-                    }
-                    if let Some(nt) = next_turn 
-                    {
-                        this_state.turn(nt);
-                        this_move *= nt.as_move();
-                    }
-                    else
-                    {
-                        if this_state.is_solved()
-                        {
-                            break
-                        }
-                        else
-                        {
-                            todo!();
-                            //return (false, None);
-                        }
-                    }
-                }
+            let found = self.state.is_solved();
+            let go_deeper = !found && depth + 1 < self.depth_limit &&
+                self.solver.calc_heuristics(&self.state, false, None).is_none_or(|h| h < self.depth_limit - depth);
 
-                return Ok(this_move);
+            if go_deeper
+            {
+                self.next_index.push(0);
+                continue;
             }
-            else
+
+            let solution = if found && depth + 1 == self.depth_limit { Some(self.path.clone()) } else { None };
+            self.path.turns.pop();
+            self.state.turn(turn.invert());
+
+            if solution.is_some()
             {
-                return Err(RubikSolveError::NoHeuristicsTable);
+                return solution;
             }
         }
-        else
+    }
+}
+
+/// The solvers that [`RubiksCubeSolver::compare`] can run.
+///
+/// [`RubiksCubeSolver::compare`]: struct.RubiksCubeSolver.html#method.compare
+#[allow(dead_code)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SolveAlgo
+{
+    /// [`RubiksCubeSolver::solve_dpll`] with `k` as the scramble length.
+    ///
+    /// [`RubiksCubeSolver::solve_dpll`]: struct.RubiksCubeSolver.html#method.solve_dpll
+    Dpll,
+    /// [`RubiksCubeSolver::solve_with_idastar`].
+    ///
+    /// [`RubiksCubeSolver::solve_with_idastar`]: struct.RubiksCubeSolver.html#method.solve_with_idastar
+    IdaStar,
+    /// The first solution from [`RubiksCubeSolver::solutions_iter`], an iterative deepening dfs.
+    ///
+    /// [`RubiksCubeSolver::solutions_iter`]: struct.RubiksCubeSolver.html#method.solutions_iter
+    Iddfs,
+}
+
+/// Which solver [`RubiksCubeSolver::solve_with_fallback`] got its answer from.
+///
+/// [`RubiksCubeSolver::solve_with_fallback`]: struct.RubiksCubeSolver.html#method.solve_with_fallback
+#[allow(dead_code)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SolverUsed
+{
+    /// [`RubiksCubeSolver::solve_capped`], so the solution is optimal.
+    ///
+    /// [`RubiksCubeSolver::solve_capped`]: struct.RubiksCubeSolver.html#method.solve_capped
+    Optimal,
+    /// [`RubiksCubeSolver::solve_thistlethwaite`].
+    ///
+    /// [`RubiksCubeSolver::solve_thistlethwaite`]: struct.RubiksCubeSolver.html#method.solve_thistlethwaite
+    Thistlethwaite,
+    /// Every solver failed, the error is from the last one tried for the cube's size.
+    Nothing,
+}
+
+/// The solvers [`RubiksCubeSolver::solve_with_fallback`] can try, each one gives its answer as a [`SolverUsed`].
+///
+/// [`RubiksCubeSolver::solve_with_fallback`]: struct.RubiksCubeSolver.html#method.solve_with_fallback
+/// [`SolverUsed`]: enum.SolverUsed.html
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum FallbackAttempt
+{
+    Optimal,
+    Thistlethwaite,
+}
+
+impl FallbackAttempt
+{
+    fn used(self) -> SolverUsed
+    {
+        match self
         {
-            return Err(RubikSolveError::NoHeuristicsTable);
+            FallbackAttempt::Optimal => SolverUsed::Optimal,
+            FallbackAttempt::Thistlethwaite => SolverUsed::Thistlethwaite,
         }
     }
+}
 
-    fn calc_corner_heuristics(&self, rubiks_state: &rubiks::RubiksCubeState) -> Option<usize>
+/// How one [`SolveAlgo`] did in [`RubiksCubeSolver::compare`].
+///
+/// [`SolveAlgo`]: enum.SolveAlgo.html
+/// [`RubiksCubeSolver::compare`]: struct.RubiksCubeSolver.html#method.compare
+#[allow(dead_code)]
+#[derive(Clone, Debug)]
+pub struct BenchReport
+{
+    pub algo: SolveAlgo,
+    /// The number of scrambles that were solved, the solution is checked.
+    pub solved: usize,
+    /// The number of scrambles that gave an error or a wrong solution.
+    pub failed: usize,
+    /// The number of turns in all the solutions.
+    pub total_turns: usize,
+    /// The time for all the scrambles, solved or not.
+    pub total_time: Duration,
+    /// The number of turns in each solution, in the order the scrambles were solved.
+    pub solution_lengths: Vec<usize>,
+}
+
+impl BenchReport
+{
+    /// The average number of turns of the solved scrambles.
+    #[allow(dead_code)]
+    pub fn mean_turns(&self) -> f64
     {
-        // make it solve the 2x2x2 with dpll if not table exists
-        if let Some(ref heuristic_table) = self.heuristic_table
+        self.total_turns as f64 / self.solved.max(1) as f64
+    }
+
+    /// The average time for each scramble.
+    #[allow(dead_code)]
+    pub fn mean_time(&self) -> Duration
+    {
+        self.total_time / (self.solved + self.failed).max(1) as u32
+    }
+
+    /// The number of solutions of each length.
+    #[allow(dead_code)]
+    pub fn length_histogram(&self) -> BTreeMap<usize, usize>
+    {
+        let mut histogram = BTreeMap::new();
+        for &len in &self.solution_lengths
         {
-            if let Some(ref corner_ht) = &heuristic_table.corners
-            {
-                let mut cube_state2 = rubiks::RubiksCubeState::from_corners_to_2x2x2(rubiks_state);
-                cube_state2.rotate_to_normal_2x2x2(); // this is for hashing // TODO: do better
-                return corner_ht.get(&cube_state2).map(|v| *v as usize);
-            }
+            *histogram.entry(len).or_insert(0) += 1;
         }
+        histogram
+    }
+}
 
-        return None;
+/// Counts from one search, see [`RubiksCubeSolver::solve_with_idastar_stats`].
+///
+/// [`RubiksCubeSolver::solve_with_idastar_stats`]: struct.RubiksCubeSolver.html#method.solve_with_idastar_stats
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct SolveStats
+{
+    /// The number of states that were searched from (over all the bounds).
+    pub nodes: usize,
+    /// The number of turns skipped because they went back to a state already on the current path.
+    pub cycles_pruned: usize,
+}
 
-        // todo!() //Self::from_corners_to_2x2x2(cube_state, (&self.heuristic_table).as_ref())
-                //.solver_dpll_2x2x2(k).1.map(|m| m.turns.len())
+/// The closest [`RubiksCubeSolver::solve_capped`] has got to solved, by the heuristic, in at most `max_moves` turns.
+///
+/// [`RubiksCubeSolver::solve_capped`]: struct.RubiksCubeSolver.html#method.solve_capped
+struct CappedBest
+{
+    max_moves: usize,
+    heuristic: usize,
+    turns: rubiks::Move,
+}
+
+/// What one [`RubiksCubeSolver::idastar`] search keeps as it goes, see [`RubiksCubeSolver::idastar_search`].
+///
+/// [`RubiksCubeSolver::idastar`]: struct.RubiksCubeSolver.html#method.idastar
+/// [`RubiksCubeSolver::idastar_search`]: struct.RubiksCubeSolver.html#method.idastar_search
+struct IdaStarSearch<'a>
+{
+    /// The state we are at, the turns are done and undone on it instead of cloning it for every node.
+    state: rubiks::RubiksCubeState,
+    /// The turns from the start to `state`.
+    rubiks_move: rubiks::Move,
+    all_turns: Vec<rubiks::Turn>,
+    heuristics_table: Option<HashMap<rubiks::RubiksCubeState, usize>>,
+    /// The rotation-normalized states from the start to `state` when cycle detection is on.
+    path: Option<Vec<rubiks::RubiksCubeState>>,
+    stats: SolveStats,
+    /// The lowest heuristic seen within its `max_moves` and the turns to it, when the search is capped.
+    best: Option<CappedBest>,
+    /// Called before going to each next state.
+    trace_sink: Option<&'a mut dyn FnMut(usize, Option<rubiks::Turn>, usize)>,
+}
+
+/// Why one turn of a solution was picked, see [`RubiksCubeSolver::solve_with_idastar_traced`].
+/// The heuristic is the estimated number of turns left, `None` if there are no heuristics for the cube.
+///
+/// [`RubiksCubeSolver::solve_with_idastar_traced`]: struct.RubiksCubeSolver.html#method.solve_with_idastar_traced
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MoveTrace
+{
+    pub turn: rubiks::Turn,
+    pub heuristic_before: Option<usize>,
+    pub heuristic_after: Option<usize>,
+}
+
+// This lives here and not with the rest of `Move` since it needs the solver.
+impl rubiks::Move
+{
+    /// Gives a move that takes `from` to `to`, like replaying someone else's moves on your own cube.
+    /// This is [`RubiksCubeSolver::solve_to`] with [`gods_number`] in the quarter turn metric as `k`,
+    /// cubes where it isn't known have no limit.
+    ///
+    /// [`RubiksCubeSolver::solve_to`]: struct.RubiksCubeSolver.html#method.solve_to
+    /// [`gods_number`]: fn.gods_number.html
+    #[allow(dead_code)]
+    pub fn between(from: &rubiks::RubiksCubeState, to: &rubiks::RubiksCubeState, solver: &RubiksCubeSolver) -> Result<rubiks::Move, RubikSolveError>
+    {
+        let k = gods_number(from.size(), Metric::Quarter).unwrap_or(usize::MAX);
+        solver.solve_to(from, to, k)
     }
+}
 
-    fn calc_heuristics(&self, rubiks_state: &rubiks::RubiksCubeState, solve_smaller: bool, bound: Option<usize>) -> Option<usize>
+// Same as for `Move`, this needs the solver.
+impl rubiks::RubiksCubeState
+{
+    /// A random nxnxn state that is exactly `distance` quarter turns from solved, as found by
+    /// [`RubiksCubeSolver::optimal_distance`]. The same `seed` always gives the same state.
+    ///
+    /// This scrambles with `distance` random turns and tries again until the scramble can't be solved in fewer.
+    /// For the 2x2x2 with a corner table each try is a lookup, so it is quick, but states close to [`gods_number`]
+    /// are rare and can take tens of thousands of tries. Other sizes solve every try with IDA*, so only small distances
+    /// are practical. Panics if `distance` is more than [`gods_number`] or the solver can't find the distance.
+    ///
+    /// [`RubiksCubeSolver::optimal_distance`]: struct.RubiksCubeSolver.html#method.optimal_distance
+    /// [`gods_number`]: fn.gods_number.html
+    #[allow(dead_code)]
+    pub fn rnd_at_distance(n: usize, distance: usize, seed: u64, solver: &RubiksCubeSolver) -> rubiks::RubiksCubeState
     {
-        // take max of all heuristics
-        let mut heuristics = vec![self.calc_corner_heuristics(rubiks_state)?];
+        assert!(distance <= gods_number(n, Metric::Quarter).unwrap_or(usize::MAX), "no {0}x{0}x{0} state is {1} turns from solved", n, distance);
 
-        if let Some(bound) = bound
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        loop
         {
-            if heuristics.iter().cloned().fold(heuristics[0], usize::max) > bound
+            let state = rubiks::RubiksCubeState::std_solved_nxnxn(n).do_move_copy(&rubiks::Move::rnd_move_with_rng(n, distance, &mut rng));
+            let found = solver.optimal_distance(&state).expect("the solver couldn't find the distance");
+            if found == distance
             {
-                return Some(heuristics.iter().cloned().fold(heuristics[0], usize::max))
+                return state;
             }
         }
+    }
+}
 
-        if solve_smaller && rubiks_state.size() > 4 && rubiks_state.size() != 6  // 2x2x2 cube is the same as the corner heuristic
+#[derive(Debug)]
+pub enum RubikSolveError
+{
+    Unsolveable,
+    BadInput,
+    NoHeuristicsTable,
+    /// The cancel flag given to [`RubiksCubeSolver::set_cancel_flag`] was set during the search.
+    ///
+    /// [`RubiksCubeSolver::set_cancel_flag`]: struct.RubiksCubeSolver.html#method.set_cancel_flag
+    Cancelled,
+    /// A solution was found but doing it didn't solve the cube, see [`RubiksCubeSolver::solve_verified`].
+    /// This is a bug in the solver.
+    ///
+    /// [`RubiksCubeSolver::solve_verified`]: struct.RubiksCubeSolver.html#method.solve_verified
+    WrongSolution,
+    /// No solution fits in the number of turns given to [`RubiksCubeSolver::solve_capped`]. Holds the turns, within
+    /// the cap, to the state with the lowest heuristic the search reached, so the closest it got to solved.
+    ///
+    /// [`RubiksCubeSolver::solve_capped`]: struct.RubiksCubeSolver.html#method.solve_capped
+    #[allow(dead_code)]
+    BestEffort(rubiks::Move),
+}
+
+/// A rough rating of how hard a cube state is, see [`RubiksCubeSolver::difficulty`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Difficulty
+{
+    Easy,
+    Medium,
+    Hard,
+}
+
+/// The thresholds used to turn an estimated distance into a [`Difficulty`].
+/// A state is `Easy` if the estimated distance is at most `easy_max`, `Medium` if it is at most `medium_max`
+/// and `Hard` otherwise.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DifficultyConfig
+{
+    pub easy_max: usize,
+    pub medium_max: usize,
+}
+
+impl DifficultyConfig
+{
+    /// The default thresholds for a nxnxn cube.
+    ///
+    /// For the 2x2x2 the estimate is exact (0 to 14 quarter turns) so the thresholds are 8 and 11.
+    /// For bigger cubes the estimate comes from the corners which is at most 14 quarter turns, so the thresholds are 7 and 10.
+    pub fn for_size(n: usize) -> Self
+    {
+        if n <= 2
         {
-            //let rubiks_state_smaller2 = rubiks_state.from_outer_to_smaller_cube_size(rubiks_state.size() - 2);
-            let rubiks_state_smaller2 = if rubiks_state.size() % 2 == 1 {rubiks_state.from_outer_to_smaller_cube_size(3)}
-            else {rubiks_state.from_outer_to_smaller_cube_size(4)};
-            if let Ok(turns) = self.solve_with_idastar(&rubiks_state_smaller2)
-            {
-                heuristics.push(turns.turns.len());
-            }
+            DifficultyConfig{easy_max: 8, medium_max: 11}
+        }
+        else
+        {
+            DifficultyConfig{easy_max: 7, medium_max: 10}
         }
-
-        return Some(heuristics.iter().cloned().fold(heuristics[0], usize::max));
     }
 
-    /// will use heuristics if available
-    pub fn solve_dpll(&self, rubiks_state: &rubiks::RubiksCubeState, k: usize) -> Result<rubiks::Move, RubikSolveError>
+    pub fn classify(&self, estimated_distance: usize) -> Difficulty
     {
-        if rubiks_state.is_solved()
+        if estimated_distance <= self.easy_max
         {
-            return Ok(rubiks::Move::empty());
+            Difficulty::Easy
         }
-        else if k <= 0
+        else if estimated_distance <= self.medium_max
         {
-            return Err(RubikSolveError::Unsolveable);
+            Difficulty::Medium
         }
-    
-        // if !valid
-        // {
-        //     return (false, None);
-        // }
-    
-        let mut state_history: Vec<Option<(rubiks::Move, rubiks::RubiksCubeState)>> = vec![None ; k+1];
-        state_history[0] = Some((rubiks::Move::empty(), rubiks_state.clone()));
-        let mut possible_turns: Vec<(usize, rubiks::Turn)> = vec![];
-    
-        for turn_type in rubiks_state.all_turns()
+        else
         {
-            possible_turns.push((1, turn_type))
+            Difficulty::Hard
         }
-    
-        while let Some((i, rubiks_turn)) = possible_turns.pop()
-        {
-            // do turn, add to history
-            let mut mut_move = (&state_history[i-1]).as_ref().unwrap().0.clone();
-            let mut mut_state = (&state_history[i-1]).as_ref().unwrap().1.clone();
-            mut_state.turn(rubiks_turn);
-            mut_move.turns.push(rubiks_turn);
-            state_history[i] = Some((mut_move, mut_state));
-    
-            if state_history[i].as_ref().unwrap().1.is_solved()
-            {
-                return Ok(state_history[i].as_ref().unwrap().0.clone());
-            }
-    
-            if i >= k
-            {
-                // just made kth move and it was not solved
-                continue;
-            }
-    
-            // TODO: update to use a general smaller cube, not just 2x2x2
-            if rubiks_state.size() > 2 && k-i < 14 // note: every 2x2x2 cube can be solved in 14 moves or less
+    }
+}
+
+/// One phase of a multi-phase solver, see [`RubiksCubeSolver::solve_phases`]. A phase takes the cube into a smaller group
+/// of states, its `goal`, using moves that keep the cube in the group the phases before it ended in.
+///
+/// [`RubiksCubeSolver::solve_phases`]: struct.RubiksCubeSolver.html#method.solve_phases
+pub trait PhaseSolver
+{
+    /// True once `state` is where this phase takes it.
+    fn goal(&self, state: &rubiks::RubiksCubeState) -> bool;
+
+    /// The moves this phase can do from `state`, each one counts as a single step of the search.
+    fn moves(&self, state: &rubiks::RubiksCubeState) -> Vec<rubiks::Move>;
+
+    /// A lower bound on the number of `moves` to reach the `goal`. The default is 0, which makes the search an
+    /// iterative deepening dfs, so this is only needed for phases that take more than a few moves.
+    fn heuristic(&self, _state: &rubiks::RubiksCubeState) -> usize
+    {
+        0
+    }
+}
+
+/// The four phases of Thistlethwaite's algorithm for the 3x3x3, in order. Each one takes the cube into a smaller group:
+/// any cube (G0) to `<U, D, L, R, F2, B2>` (G1) to `<U, D, L2, R2, F2, B2>` (G2) to `<U2, D2, L2, R2, F2, B2>` (G3) to
+/// solved, using only the moves of the group it starts in. Use them with [`RubiksCubeSolver::solve_phases`].
+///
+/// [`RubiksCubeSolver::solve_phases`]: struct.RubiksCubeSolver.html#method.solve_phases
+#[allow(dead_code)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ThistlethwaitePhase
+{
+    /// G0 to G1, no edge is flipped (see [`rubiks::Cubies`]).
+    ///
+    /// [`rubiks::Cubies`]: ../rubiks/struct.Cubies.html
+    EdgeOrientation,
+    /// G1 to G2, no corner is twisted and the middle layer edges (FL, FR, BR, BL) are in the middle layer.
+    CornerOrientation,
+    /// G2 to G3, the other edges are in their slices and the corners are where half turns can solve them.
+    HalfTurnReduction,
+    /// G3 to solved.
+    HalfTurnSolve,
+}
+
+impl ThistlethwaitePhase
+{
+    #[allow(dead_code)]
+    pub fn all() -> [ThistlethwaitePhase; 4]
+    {
+        [ThistlethwaitePhase::EdgeOrientation, ThistlethwaitePhase::CornerOrientation,
+         ThistlethwaitePhase::HalfTurnReduction, ThistlethwaitePhase::HalfTurnSolve]
+    }
+
+    /// Every corner permutation that half turns can make, as the `corner_perm` of [`rubiks::Cubies`]. There are 96 of
+    /// them, found once with a bfs of half turns on a solved cube.
+    ///
+    /// [`rubiks::Cubies`]: ../rubiks/struct.Cubies.html
+    fn half_turn_corner_perms() -> &'static HashSet<[u8; 8]>
+    {
+        static PERMS: std::sync::OnceLock<HashSet<[u8; 8]>> = std::sync::OnceLock::new();
+
+        PERMS.get_or_init(||
+        {
+            let half_turns = ThistlethwaitePhase::HalfTurnSolve.moves(&rubiks::RubiksCubeState::std_solved_nxnxn(3));
+            let solved = rubiks::RubiksCubeState::std_solved_nxnxn(3);
+
+            // the corners only depend on the corners before the turn, so one state per corner permutation is enough
+            let mut perms = HashSet::new();
+            let mut vq = VecDeque::new();
+            perms.insert(solved.cubies().unwrap().corner_perm);
+            vq.push_back(solved);
+            while let Some(state) = vq.pop_front()
             {
-                //if there are no heuristics, we can't do anything
-                //if let Some(h_val) = self.calc_corner_heuristics(&state_history[i].as_ref().unwrap().1)
-                if let Some(h_val) = self.calc_heuristics(&state_history[i].as_ref().unwrap().1, false, None)
+                for half_turn in &half_turns
                 {
-                    if h_val > k-1
+                    let next_state = state.do_move_copy(half_turn);
+                    if perms.insert(next_state.cubies().unwrap().corner_perm)
                     {
-                        // our lower bound is to high
-                        continue;
+                        vq.push_back(next_state);
                     }
                 }
             }
-    
-            for turn_type in rubiks_state.all_turns()
+            perms
+        })
+    }
+}
+
+/// The distance of each phase's coordinate from the phase's goal, in the phase's moves, for [`ThistlethwaitePhase`]'s
+/// heuristics. A coordinate is the part of the cube a phase changes, numbered so it can index a table:
+///
+/// 1. which edges are flipped, `2^11` values
+/// 2. the corner twists and which positions the middle layer edges are in, `3^7 * 12C4` values
+/// 3. the corner permutation and which of the top and bottom layer positions the M slice edges are in, `8! * 8C4` values
+/// 4. the corner permutation out of the 96 half turns can make and the order of the edges in each slice, `96 * 24^3` values
+///
+/// Each coordinate only depends on the coordinate before a move, so the moves are worked out once per coordinate value,
+/// from the pieces a move does on a solved cube, and each table is a bfs from the goal over those.
+///
+/// [`ThistlethwaitePhase`]: enum.ThistlethwaitePhase.html
+struct ThistlethwaiteTables
+{
+    slice_ranks: Vec<usize>,
+    m_slice_ranks: Vec<usize>,
+    edge_flips: Vec<u8>,
+    twists_and_slice: Vec<u8>,
+    corners_and_m_slice: Vec<u8>,
+    half_turns: Vec<u8>,
+    half_turn_corner_index: HashMap<[u8; 8], usize>,
+}
+
+impl ThistlethwaiteTables
+{
+    const SLICE_COMBINATIONS: usize = 495;
+    const M_SLICE_COMBINATIONS: usize = 70;
+    const SLICE_ORDERS: usize = 24 * 24 * 24;
+    /// The edge positions of each slice, M (between L and R), S (between F and B) and E (between U and D).
+    const SLICES: [[usize; 4]; 3] = [[0, 3, 8, 11], [1, 2, 9, 10], [4, 5, 6, 7]];
+    /// The edge positions out of the E slice, a bit each in the M slice mask.
+    const NOT_E_SLICE: [usize; 8] = [0, 1, 2, 3, 8, 9, 10, 11];
+
+    fn get() -> &'static ThistlethwaiteTables
+    {
+        static TABLES: std::sync::OnceLock<ThistlethwaiteTables> = std::sync::OnceLock::new();
+
+        TABLES.get_or_init(ThistlethwaiteTables::build)
+    }
+
+    fn build() -> ThistlethwaiteTables
+    {
+        let solved = rubiks::RubiksCubeState::std_solved_nxnxn(3);
+        let phase_moves = |phase: ThistlethwaitePhase| -> Vec<rubiks::Cubies>
+        {
+            phase.moves(&solved).iter().map(|phase_move| solved.do_move_copy(phase_move).cubies().unwrap()).collect()
+        };
+        // the next value of a coordinate for every value and move, from turning pieces that have that value
+        let transitions = |size: usize, moves: &[rubiks::Cubies], decode: &dyn Fn(usize) -> rubiks::Cubies,
+            encode: &dyn Fn(&rubiks::Cubies) -> usize| -> Vec<usize>
+        {
+            (0..size).flat_map(|value| { let cubies = decode(value); moves.iter().map(move |turned| encode(&cubies.then(turned))) })
+                .collect()
+        };
+        let (ranks, masks) = Self::combinations(12);
+        let (m_ranks, m_masks) = Self::combinations(8);
+
+        let moves = phase_moves(ThistlethwaitePhase::EdgeOrientation);
+        let flip_moves = transitions(1 << 11, &moves, &Self::from_edge_flips, &Self::edge_flips);
+        let edge_flips = Self::bfs(1 << 11, &[0], moves.len(), |value, m| flip_moves[value * moves.len() + m]);
+
+        let moves = phase_moves(ThistlethwaitePhase::CornerOrientation);
+        let twist_moves = transitions(2187, &moves, &Self::from_twists, &Self::twists);
+        let slice_moves = transitions(Self::SLICE_COMBINATIONS, &moves, &|rank| Self::from_slice(masks[rank]),
+                                      &|cubies| ranks[Self::slice_mask(cubies)]);
+        let solved_slice = ranks[Self::slice_mask(&rubiks::Cubies::solved())];
+        let twists_and_slice = Self::bfs(2187 * Self::SLICE_COMBINATIONS, &[solved_slice], moves.len(), |value, m|
+        {
+            let (twists, slice) = (value / Self::SLICE_COMBINATIONS, value % Self::SLICE_COMBINATIONS);
+            twist_moves[twists * moves.len() + m] * Self::SLICE_COMBINATIONS + slice_moves[slice * moves.len() + m]
+        });
+
+        let moves = phase_moves(ThistlethwaitePhase::HalfTurnReduction);
+        let corner_moves = transitions(40320, &moves, &Self::from_corner_perm, &|cubies| Self::perm_index(&cubies.corner_perm));
+        let m_slice_moves = transitions(Self::M_SLICE_COMBINATIONS, &moves, &|rank| Self::from_m_slice(m_masks[rank]),
+                                        &|cubies| m_ranks[Self::m_slice_mask(cubies)]);
+        let half_turn_perms: Vec<[u8; 8]> = ThistlethwaitePhase::half_turn_corner_perms().iter().copied().collect();
+        let solved_m_slice = m_ranks[Self::m_slice_mask(&rubiks::Cubies::solved())];
+        let goals: Vec<usize> = half_turn_perms.iter()
+            .map(|perm| Self::perm_index(perm) * Self::M_SLICE_COMBINATIONS + solved_m_slice).collect();
+        let corners_and_m_slice = Self::bfs(40320 * Self::M_SLICE_COMBINATIONS, &goals, moves.len(), |value, m|
+        {
+            let (corners, m_slice) = (value / Self::M_SLICE_COMBINATIONS, value % Self::M_SLICE_COMBINATIONS);
+            corner_moves[corners * moves.len() + m] * Self::M_SLICE_COMBINATIONS + m_slice_moves[m_slice * moves.len() + m]
+        });
+
+        let moves = phase_moves(ThistlethwaitePhase::HalfTurnSolve);
+        let half_turn_corner_index: HashMap<[u8; 8], usize> = half_turn_perms.iter().enumerate().map(|(i, &perm)| (perm, i)).collect();
+        let half_turn_corner_moves = transitions(half_turn_perms.len(), &moves,
+            &|i| rubiks::Cubies{corner_perm: half_turn_perms[i], ..rubiks::Cubies::solved()},
+            &|cubies| half_turn_corner_index[&cubies.corner_perm]);
+        let order_moves = transitions(Self::SLICE_ORDERS, &moves, &Self::from_slice_orders, &|cubies| Self::slice_orders(cubies).unwrap());
+        let solved_corners = half_turn_corner_index[&rubiks::Cubies::solved().corner_perm];
+        let half_turns = Self::bfs(half_turn_perms.len() * Self::SLICE_ORDERS, &[solved_corners * Self::SLICE_ORDERS], moves.len(),
+        |value, m|
+        {
+            let (corners, orders) = (value / Self::SLICE_ORDERS, value % Self::SLICE_ORDERS);
+            half_turn_corner_moves[corners * moves.len() + m] * Self::SLICE_ORDERS + order_moves[orders * moves.len() + m]
+        });
+
+        ThistlethwaiteTables{slice_ranks: ranks, m_slice_ranks: m_ranks, edge_flips, twists_and_slice, corners_and_m_slice, half_turns, half_turn_corner_index}
+    }
+
+    /// The number of moves from any of the `goals` to each value, `u8::MAX` if it can't be reached.
+    fn bfs(size: usize, goals: &[usize], num_moves: usize, next: impl Fn(usize, usize) -> usize) -> Vec<u8>
+    {
+        let mut table = vec![u8::MAX; size];
+        let mut vq = VecDeque::new();
+        for &goal in goals
+        {
+            table[goal] = 0;
+            vq.push_back(goal);
+        }
+
+        while let Some(value) = vq.pop_front()
+        {
+            for m in 0..num_moves
             {
-                if !state_history[i].as_ref().unwrap().0.is_next_turn_efficient(turn_type)
+                let next_value = next(value, m);
+                if table[next_value] == u8::MAX
                 {
-                    continue;
+                    table[next_value] = table[value] + 1;
+                    vq.push_back(next_value);
                 }
-    
-                possible_turns.push((i + 1, turn_type));
             }
         }
-    
-        return Err(RubikSolveError::Unsolveable);
+
+        table
     }
 
-    fn get_heuristic_from_table_or_calc(&self, this_heuristics_table: &mut Option<HashMap<rubiks::RubiksCubeState, usize>>,
-        state: &rubiks::RubiksCubeState, g: usize, solve_smaller: bool, bound: Option<usize>)
-        -> Option<usize>
+    /// The rank of every `n` bit mask with 4 bits set (`usize::MAX` for the others) and the masks in rank order.
+    fn combinations(n: usize) -> (Vec<usize>, Vec<usize>)
     {
-        if g < 7  // todo calc from cube size
+        let masks: Vec<usize> = (0..1 << n).filter(|mask: &usize| mask.count_ones() == 4).collect();
+        let mut ranks = vec![usize::MAX; 1 << n];
+        for (rank, &mask) in masks.iter().enumerate()
         {
-            if let Some(this_table) = this_heuristics_table.as_mut()
-            {
-                if let Some(&val_in_table) = this_table.get(&state)
-                {
-                    Some(val_in_table)
-                }
-                else
-                {
-                    let val = self.calc_heuristics(state, solve_smaller, bound);
-                    if let Some(num) = val
-                    {
-                        this_table.insert(state.clone(), num);
-                    }
-                    val
-                }
-            }
-            else
-            {
-                self.calc_heuristics(state, solve_smaller, bound)
-            }
+            ranks[mask] = rank;
         }
-        else
+        (ranks, masks)
+    }
+
+    /// The index of `perm` out of all permutations of its length, in lexicographic order.
+    fn perm_index(perm: &[u8]) -> usize
+    {
+        (0..perm.len()).fold(0, |index, i| index * (perm.len() - i) + perm[i + 1..].iter().filter(|&&p| p < perm[i]).count())
+    }
+
+    fn perm_from_index(mut index: usize, len: usize) -> Vec<u8>
+    {
+        let mut digits = vec![0; len];
+        for i in (0..len).rev()
         {
-            self.calc_heuristics(state, solve_smaller, bound)
+            digits[i] = index % (len - i);
+            index /= len - i;
         }
+        let mut remaining: Vec<u8> = (0..len as u8).collect();
+        digits.iter().map(|&digit| remaining.remove(digit)).collect()
     }
 
-    #[allow(dead_code)]
-    pub fn solve_with_idastar(&self, rubiks_state: &rubiks::RubiksCubeState) -> Result<rubiks::Move, RubikSolveError>
+    fn edge_flips(cubies: &rubiks::Cubies) -> usize
     {
-        let mut this_heuristics_table: Option<HashMap<rubiks::RubiksCubeState, usize>> = if rubiks_state.size() > 4
+        (0..11).map(|pos| (cubies.edge_flip[pos] as usize) << pos).sum()
+    }
+
+    fn from_edge_flips(value: usize) -> rubiks::Cubies
+    {
+        let mut cubies = rubiks::Cubies::solved();
+        for pos in 0..11
         {
-            // if the size is greater than we use more than just the basic corner heuristics
-            Some(HashMap::with_capacity(4000000)) // TODO: pick better size and should we use usize or something smaller
+            cubies.edge_flip[pos] = ((value >> pos) & 1) as u8;
         }
-        else
+        // the flips always add up to an even number
+        cubies.edge_flip[11] = cubies.edge_flip.iter().sum::<u8>() % 2;
+        cubies
+    }
+
+    fn twists(cubies: &rubiks::Cubies) -> usize
+    {
+        (0..7).rev().fold(0, |value, pos| value * 3 + cubies.corner_twist[pos] as usize)
+    }
+
+    fn from_twists(mut value: usize) -> rubiks::Cubies
+    {
+        let mut cubies = rubiks::Cubies::solved();
+        for pos in 0..7
         {
-            None
-        };
-    
-        // ida star that uses smaller cubes as the heuristic
-        let start_h = self.get_heuristic_from_table_or_calc(&mut this_heuristics_table, rubiks_state, 0, true, None)
-                                .ok_or(RubikSolveError::NoHeuristicsTable)?;
-        let mut bound = start_h;
-        // println!("new bound: {}", bound);
+            cubies.corner_twist[pos] = (value % 3) as u8;
+            value /= 3;
+        }
+        // the twists always add up to a multiple of 3
+        cubies.corner_twist[7] = (3 - cubies.corner_twist.iter().sum::<u8>() % 3) % 3;
+        cubies
+    }
 
-        let mut state_stack: Vec<(rubiks::Move, rubiks::RubiksCubeState, usize)> = vec![]; //vec![None ; k+1]; // TODO: with cap
+    /// A bit for each position that has a middle layer edge.
+    fn slice_mask(cubies: &rubiks::Cubies) -> usize
+    {
+        (0..12).filter(|&pos| Self::SLICES[2].contains(&(cubies.edge_perm[pos] as usize))).map(|pos| 1 << pos).sum()
+    }
 
-        loop
+    /// Puts the middle layer edges in the positions of `mask` and the others in the rest, both in order.
+    fn from_slice(mask: usize) -> rubiks::Cubies
+    {
+        let mut cubies = rubiks::Cubies::solved();
+        let (mut slice_edges, mut other_edges) = (Self::SLICES[2].iter(), (0..12).filter(|edge| !Self::SLICES[2].contains(edge)));
+        for pos in 0..12
         {
-            let mut min_turns: Option<usize> = None;
-            state_stack.push((rubiks::Move::empty(), rubiks_state.clone(), start_h));
+            let edge = if mask & (1 << pos) != 0 { *slice_edges.next().unwrap() } else { other_edges.next().unwrap() };
+            cubies.edge_perm[pos] = edge as u8;
+        }
+        cubies
+    }
 
-            while let Some((rubiks_move, curr_state, _)) = {state_stack.sort_by_key(|a| a.2); state_stack.pop()}
+    /// A bit for each position out of the middle layer, in the order of `NOT_E_SLICE`, that has an M slice edge.
+    fn m_slice_mask(cubies: &rubiks::Cubies) -> usize
+    {
+        (0..8).filter(|&i| Self::SLICES[0].contains(&(cubies.edge_perm[Self::NOT_E_SLICE[i]] as usize))).map(|i| 1 << i).sum()
+    }
+
+    fn from_m_slice(mask: usize) -> rubiks::Cubies
+    {
+        let mut cubies = rubiks::Cubies::solved();
+        let (mut m_edges, mut s_edges) = (Self::SLICES[0].iter(), Self::SLICES[1].iter());
+        for (i, &pos) in Self::NOT_E_SLICE.iter().enumerate()
+        {
+            let edge = if mask & (1 << i) != 0 { m_edges.next().unwrap() } else { s_edges.next().unwrap() };
+            cubies.edge_perm[pos] = *edge as u8;
+        }
+        cubies
+    }
+
+    fn from_corner_perm(index: usize) -> rubiks::Cubies
+    {
+        let mut cubies = rubiks::Cubies::solved();
+        cubies.corner_perm.copy_from_slice(&Self::perm_from_index(index, 8));
+        cubies
+    }
+
+    /// The order of the edges in each slice, as the index of each slice's permutation (see [`perm_index`]) in base 24.
+    /// `None` if an edge isn't in its slice.
+    ///
+    /// [`perm_index`]: struct.ThistlethwaiteTables.html#method.perm_index
+    fn slice_orders(cubies: &rubiks::Cubies) -> Option<usize>
+    {
+        let mut value = 0;
+        for slice in Self::SLICES.iter()
+        {
+            let order: Option<Vec<u8>> = slice.iter()
+                .map(|&pos| slice.iter().position(|&edge| edge == cubies.edge_perm[pos] as usize).map(|i| i as u8)).collect();
+            value = value * 24 + Self::perm_index(&order?);
+        }
+        Some(value)
+    }
+
+    fn from_slice_orders(mut value: usize) -> rubiks::Cubies
+    {
+        let mut cubies = rubiks::Cubies::solved();
+        for slice in Self::SLICES.iter().rev()
+        {
+            for (pos, i) in slice.iter().zip(Self::perm_from_index(value % 24, 4))
+            {
+                cubies.edge_perm[*pos] = slice[i as usize] as u8;
+            }
+            value /= 24;
+        }
+        cubies
+    }
+
+    /// The number of moves `phase` needs to reach its goal from `cubies`, 0 if the cube isn't in the group the phase starts in.
+    fn distance(&self, phase: ThistlethwaitePhase, cubies: &rubiks::Cubies) -> usize
+    {
+        let distance = match phase
+        {
+            ThistlethwaitePhase::EdgeOrientation => self.edge_flips[Self::edge_flips(cubies)],
+            ThistlethwaitePhase::CornerOrientation =>
+                self.twists_and_slice[Self::twists(cubies) * Self::SLICE_COMBINATIONS + self.slice_ranks[Self::slice_mask(cubies)]],
+            ThistlethwaitePhase::HalfTurnReduction =>
             {
-                // let curr_h = self.calc_heuristics(&curr_state, true).ok_or(RubikSolveError::NoHeuristicsTable)?;
-                let curr_g = rubiks_move.turns.len();
-                //let f = curr_g + curr_h;
-                
-                if curr_state.is_solved()
+                match self.m_slice_ranks[Self::m_slice_mask(cubies)]
                 {
-                    return Ok(rubiks_move.clone());
+                    usize::MAX => 0,
+                    m_slice => self.corners_and_m_slice[Self::perm_index(&cubies.corner_perm) * Self::M_SLICE_COMBINATIONS + m_slice],
                 }
-
-                for turn_type in rubiks_state.all_turns().into_iter().filter(|turn_type|
-                                                            rubiks_move.is_next_turn_efficient(*turn_type))
+            },
+            ThistlethwaitePhase::HalfTurnSolve =>
+            {
+                match (self.half_turn_corner_index.get(&cubies.corner_perm), Self::slice_orders(cubies))
                 {
-                    let mut mut_move = rubiks_move.clone();
-                    let mut mut_state = curr_state.clone();
-                    mut_state.turn(turn_type);
-                    mut_move.turns.push(turn_type);
-
-                    assert_eq!(curr_g + 1, mut_move.turns.len());
-                    let next_g = curr_g + 1;
-                    let next_h = self.get_heuristic_from_table_or_calc(&mut this_heuristics_table, &mut_state, next_g, true, min_turns.map(|val| val - next_g))
-                                            .ok_or(RubikSolveError::NoHeuristicsTable)?;
-                    let next_f = next_g + next_h;
-
-                    if next_f > bound
-                    {
-                        if let Some(num_min_turns) = min_turns
-                        {
-                            if next_f < num_min_turns
-                            {
-                                min_turns = Some(next_f)
-                            }
-                        }
-                        else
-                        {
-                            min_turns = Some(next_f)
-                        }
-                    }
-                    else
-                    {
-                        // TODO: check if the mut_state has already been reached maybe (at least in the path)
-                        state_stack.push((mut_move, mut_state, next_f));
-                    }
+                    (Some(&corners), Some(orders)) => self.half_turns[corners * Self::SLICE_ORDERS + orders],
+                    _ => 0,
                 }
-            }
+            },
+        };
+        if distance == u8::MAX { 0 } else { distance as usize }
+    }
+}
 
-            if let Some(num_min_turns) = min_turns
-            {
-                bound = num_min_turns;
-                // println!("new bound: {}", bound);
-            }
-            else
+impl PhaseSolver for ThistlethwaitePhase
+{
+    /// Each goal includes the goals of the phases before it, so it says the cube is in the group the phase ends in.
+    /// Always false if the cube isn't a 3x3x3 that can be solved.
+    fn goal(&self, state: &rubiks::RubiksCubeState) -> bool
+    {
+        let cubies = match state.cubies()
+        {
+            Some(cubies) => cubies,
+            None => return false,
+        };
+        // the positions of the edges in the middle layer (E) and between the left and right faces (M)
+        let in_slice = |positions: &[u8]| positions.iter().all(|&pos| positions.contains(&cubies.edge_perm[pos as usize]));
+
+        match self
+        {
+            ThistlethwaitePhase::EdgeOrientation => cubies.edge_flip.iter().all(|&flip| flip == 0),
+            ThistlethwaitePhase::CornerOrientation => ThistlethwaitePhase::EdgeOrientation.goal(state)
+                && cubies.corner_twist.iter().all(|&twist| twist == 0) && in_slice(&[4, 5, 6, 7]),
+            ThistlethwaitePhase::HalfTurnReduction => ThistlethwaitePhase::CornerOrientation.goal(state)
+                && in_slice(&[0, 3, 8, 11]) && Self::half_turn_corner_perms().contains(&cubies.corner_perm),
+            ThistlethwaitePhase::HalfTurnSolve => state.is_solved(),
+        }
+    }
+
+    /// The turns that generate the group the phase starts in, as quarter and half turns.
+    fn moves(&self, _state: &rubiks::RubiksCubeState) -> Vec<rubiks::Move>
+    {
+        let quarter_faces = match self
+        {
+            ThistlethwaitePhase::EdgeOrientation => rubiks::Face::all().to_vec(),
+            ThistlethwaitePhase::CornerOrientation => vec![rubiks::Face::Up, rubiks::Face::Down, rubiks::Face::Left, rubiks::Face::Right],
+            ThistlethwaitePhase::HalfTurnReduction => vec![rubiks::Face::Up, rubiks::Face::Down],
+            ThistlethwaitePhase::HalfTurnSolve => vec![],
+        };
+
+        let mut moves = vec![];
+        for &face in rubiks::Face::all().iter()
+        {
+            let amounts: &[u8] = if quarter_faces.contains(&face) { &[1, 3, 2] } else { &[2] };
+            for &amount in amounts
             {
-                return Err(RubikSolveError::Unsolveable)
+                moves.push(rubiks::Move{turns: rubiks::HtmTurn{face, amount, num_in: 0, cube_size: 3}.to_turns()});
             }
         }
+        moves
     }
 
-    #[allow(dead_code)]
-    pub fn solve_best_approximation(&self, rubiks_state: &rubiks::RubiksCubeState) -> Result<rubiks::Move, RubikSolveError>
+    /// The exact number of moves to the goal, looked up in tables that are built the first time any phase needs them
+    /// (a few seconds). 0 if the cube isn't in the group the phase starts in.
+    fn heuristic(&self, state: &rubiks::RubiksCubeState) -> usize
     {
-        todo!()
+        state.cubies().map_or(0, |cubies| ThistlethwaiteTables::get().distance(*self, &cubies))
     }
 }
 
-// #[test]
-// fn test_calc_heuristics_table()
-// {
-//     assert!(false);
-//     let (r_state, _scram_move) = rubiks::RubiksCubeState::rnd_scramble(2, 1000);
-//
-//     let mut solver = RubiksCubeSolver::from_state(r_state.clone());
-//     solver.calc_heuristics_table();
-//
-//     //println!("moves away: {:?}", solver.calc_heuristics(&r_state, 14));
-//     assert!(solver.calc_heuristics(&r_state, 14).unwrap() <= 14);
-//
-//     for _ in 0..100
-//     {
-//         let (r_state2, _scram_move) = rubiks::RubiksCubeState::rnd_scramble(2, 1000);
-//         let num = solver.calc_heuristics(&r_state2, 14).unwrap();
-//         //println!("moves away: {}", num);
-//         assert!(num <= 14);
-//
-//         if num > 1
-//         {
-//             assert_eq!(solver.calc_heuristics(&r_state2, num-1), None)
-//         }
-//     }
-// }
-//
-// #[test]
-// fn encode_bit_strings()
-// {
-//     let n = 5;
-//     let m = 3;
-//     let s = 6*n+2*m;
-//
-//     let ls: Vec<[u8; 3]> = vec![[0, 1, 1], [1, 1, 0], [1, 1, 1], [1, 0, 0], [0, 0, 0]];
-//
-//     // let n = 3;
-//     // let m = 2;
-//     // let s = 6*n+2*m;
-//
-//     // let ls: Vec<[u8; 2]> = vec![[1, 1], [0, 1], [0, 0]];
-//
-//     let bs: Vec<rubiks::Move> = ls.clone().into_iter().enumerate().map(|(i,l)| 
-//     {
-//         let mut a_i = rubiks::Move::empty();
-//         for (j, bit) in l.iter().enumerate()
-//         {
-//             if *bit != 0 
-//             { 
-//                 a_i *= rubiks::Turn::AxisBased{
-//                     axis: rubiks::Axis::X, pos_rot: true, index: (j+1) as isize, cube_size: s}.as_move();
-//             }
-//         }
-//         let z_m_i = rubiks::Turn::AxisBased{
-//                     axis: rubiks::Axis::Z, pos_rot: true, index: (m+i+1) as isize, cube_size: s}.as_move();
-//
-//         a_i.clone() * z_m_i * a_i.invert()
-//     }).collect();
-//
-//     let mut state = rubiks::RubiksCubeState::std_solved_nxnxn(s);
-//
-//     let mut a_1 = rubiks::Move::empty();
+/// A heuristic given to [`set_custom_heuristic`].
+///
+/// [`set_custom_heuristic`]: struct.RubiksCubeSolver.html#method.set_custom_heuristic
+type CustomHeuristic = Arc<dyn Fn(&rubiks::RubiksCubeState) -> Option<usize> + Send + Sync>;
+
+/// Cloning a solver is cheap, the clones share the same heuristics tables.
+#[derive(Clone)]
+pub struct RubiksCubeSolver
+{
+    //state: rubiks::RubiksCubeState,
+    heuristic_table: Option<Arc<HeuristicsTables>>,
+    custom_heuristic: Option<CustomHeuristic>,
+    cancel: Option<Arc<AtomicBool>>,
+}
+
+impl fmt::Debug for RubiksCubeSolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RubiksCubeSolver")
+         .field("heuristic_table", &self.heuristic_table)
+         .field("custom_heuristic", &self.custom_heuristic.is_some())
+         .field("cancel", &self.cancel.as_ref().map(|cancel| cancel.load(Ordering::Relaxed)))
+         .finish()
+    }
+}
+
+impl RubiksCubeSolver
+{
+    pub fn new() -> Self
+    {
+        RubiksCubeSolver{heuristic_table: None, custom_heuristic: None, cancel: None}
+    }
+
+    /// Makes a solver that uses `heuristics_table`. The table is only read by the solvers
+    /// so one table can be shared by many solvers, even on different threads.
+    #[allow(dead_code)]
+    pub fn from_shared_table(heuristics_table: Arc<HeuristicsTables>) -> Self
+    {
+        RubiksCubeSolver{heuristic_table: Some(heuristics_table), custom_heuristic: None, cancel: None}
+    }
+
+    pub fn calc_new_heuristics_table(&mut self)
+    {
+        let mut ht = HeuristicsTables::new();
+        ht.calc_corner_heuristics_table();
+
+        self.heuristic_table = Some(Arc::new(ht));
+    }
+
+    #[allow(dead_code)]
+    pub fn add_heuristics_table(&mut self, heuristics_table: HeuristicsTables)
+    {
+        if let None = self.heuristic_table
+        {
+            self.heuristic_table = Some(Arc::new(heuristics_table));
+        }
+    }
+
+    /// Adds a heuristic that is used along side the heuristics tables, the solvers use the max of all the heuristics.
+    /// `heuristic` gives a lower bound on the number of quarter turns to solve the cube or `None` if it doesn't know.
+    /// If there are no heuristics tables then this is the only heuristic.
+    ///
+    /// Note, if `heuristic` is not admissible (it can give more turns than are needed) then the solutions are no longer optimal.
+    #[allow(dead_code)]
+    pub fn set_custom_heuristic<F>(&mut self, heuristic: F)
+        where F: Fn(&rubiks::RubiksCubeState) -> Option<usize> + Send + Sync + 'static
+    {
+        self.custom_heuristic = Some(Arc::new(heuristic));
+    }
+
+    /// Once `cancel` is set to true, [`solve_dpll`] and the IDA* solvers stop and give `RubikSolveError::Cancelled`,
+    /// and [`solutions_iter`] gives no more solutions. The flag is checked at every node of the search, so they return
+    /// promptly. The solvers don't reset it, set it back to false before the next solve.
+    ///
+    /// [`solve_dpll`]: struct.RubiksCubeSolver.html#method.solve_dpll
+    /// [`solutions_iter`]: struct.RubiksCubeSolver.html#method.solutions_iter
+    #[allow(dead_code)]
+    pub fn set_cancel_flag(&mut self, cancel: Arc<AtomicBool>)
+    {
+        self.cancel = Some(cancel);
+    }
+
+    fn is_cancelled(&self) -> bool
+    {
+        self.cancel.as_ref().is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    }
+
+    pub fn solver_2x2x2_with_heuristics_table(&self, rubiks_state: &rubiks::RubiksCubeState) -> Result<rubiks::Move, RubikSolveError>
+    {
+        if rubiks_state.size() != 2 { return Err(RubikSolveError::BadInput); }
+
+        if let Some(heuristic_table) = &self.heuristic_table
+        {
+            if let Some(ref corner_ht) = &heuristic_table.corners
+            {
+                let tmp_state = rubiks::CanonicalState::new(rubiks_state);
+                if rubiks_state.is_solved()
+                {
+                    return Ok(rubiks::Move::empty());
+                }
+                else if let None = corner_ht.get(&tmp_state)
+                {
+                    return Err(RubikSolveError::Unsolveable);
+                }
+
+                let v = corner_ht.get(&tmp_state).map(|v| *v as usize).unwrap();
+
+                let mut this_state = rubiks_state.clone();
+                let mut this_move = rubiks::Move::empty();
+
+                let mut v_left = v;
+                for _ in 0..v
+                {
+                    let mut next_turn: Option<rubiks::Turn> = None;
+                    for turn_type in rubiks_state.all_turns()
+                    {
+                        let tmp_state = rubiks::CanonicalState::new(&this_state.turn_copy(turn_type));
+                        if let Some(new_v) = corner_ht.get(&tmp_state).map(|v| *v as usize)
+                        {
+                            if new_v < v_left 
+                            {
+                                next_turn = Some(turn_type);
+                                v_left = new_v;
+                                break;
+                            }
+                        }
+                    }
+                    if let Some(nt) = next_turn 
+                    {
+                        this_state.turn(nt);
+                        this_move *= nt.as_move();
+                    }
+                    else
+                    {
+                        if this_state.is_solved()
+                        {
+                            break
+                        }
+                        else
+                        {
+                            todo!();
+                            //return (false, None);
+                        }
+                    }
+                }
+
+                return Ok(this_move);
+            }
+            else
+            {
+                return Err(RubikSolveError::NoHeuristicsTable);
+            }
+        }
+        else
+        {
+            return Err(RubikSolveError::NoHeuristicsTable);
+        }
+    }
+
+    /// Solves only the corners of `rubiks_state`, a cube of any size. The corners are taken as a 2x2x2 (see
+    /// [`RubiksCubeState::from_corners_to_2x2x2`]) and solved with the corner table, then the outer face turns are
+    /// given for the full cube size. The edges and centers are left wherever the turns take them, and the corners
+    /// end up solved with each other but can be turned with the centers of odd cubes.
+    /// Gives `BadInput` for the 1x1x1, which has no turns.
+    ///
+    /// [`RubiksCubeState::from_corners_to_2x2x2`]: ../rubiks/struct.RubiksCubeState.html#method.from_corners_to_2x2x2
+    #[allow(dead_code)]
+    pub fn solve_corners_only(&self, rubiks_state: &rubiks::RubiksCubeState) -> Result<rubiks::Move, RubikSolveError>
+    {
+        if rubiks_state.size() < 2 { return Err(RubikSolveError::BadInput); }
+
+        let soln = self.solver_2x2x2_with_heuristics_table(&rubiks_state.from_corners_to_2x2x2())?;
+        Ok(soln.change_cube_size_hold_face(rubiks_state.size()))
+    }
+
+    fn calc_corner_heuristics(&self, rubiks_state: &rubiks::RubiksCubeState) -> Option<usize>
+    {
+        // make it solve the 2x2x2 with dpll if not table exists
+        if let Some(ref heuristic_table) = self.heuristic_table
+        {
+            if heuristic_table.has(TableKind::Corners)
+            {
+                return heuristic_table.corner_distance(rubiks_state).map(|v| v as usize);
+            }
+        }
+
+        return None;
+
+        // todo!() //Self::from_corners_to_2x2x2(cube_state, (&self.heuristic_table).as_ref())
+                //.solver_dpll_2x2x2(k).1.map(|m| m.turns.len())
+    }
+
+    /// Gives the value of just the `kind` table for `rubiks_state`. If the solver doesn't have that table we give
+    /// `NoHeuristicsTable`, and `BadInput` if the state is not in the table (like when it doesn't use the standard colors).
+    #[allow(dead_code)]
+    pub fn table_heuristic(&self, rubiks_state: &rubiks::RubiksCubeState, kind: TableKind) -> Result<usize, RubikSolveError>
+    {
+        if !self.heuristic_table.as_ref().is_some_and(|heuristic_table| heuristic_table.has(kind))
+        {
+            return Err(RubikSolveError::NoHeuristicsTable);
+        }
+
+        match kind
+        {
+            TableKind::Corners => self.calc_corner_heuristics(rubiks_state).ok_or(RubikSolveError::BadInput),
+            TableKind::Edges => Err(RubikSolveError::NoHeuristicsTable),
+        }
+    }
+
+    fn calc_heuristics(&self, rubiks_state: &rubiks::RubiksCubeState, solve_smaller: bool, bound: Option<usize>) -> Option<usize>
+    {
+        // take max of all heuristics
+        let mut heuristics: Vec<usize> = self.calc_corner_heuristics(rubiks_state).into_iter().collect();
+
+        if let Some(custom_heuristic) = &self.custom_heuristic
+        {
+            heuristics.extend(custom_heuristic(rubiks_state));
+        }
+
+        if heuristics.is_empty()
+        {
+            return None;
+        }
+
+        if let Some(bound) = bound
+        {
+            if heuristics.iter().cloned().fold(heuristics[0], usize::max) > bound
+            {
+                return Some(heuristics.iter().cloned().fold(heuristics[0], usize::max))
+            }
+        }
+
+        if solve_smaller && rubiks_state.size() > 4 && rubiks_state.size() != 6  // 2x2x2 cube is the same as the corner heuristic
+        {
+            //let rubiks_state_smaller2 = rubiks_state.from_outer_to_smaller_cube_size(rubiks_state.size() - 2);
+            let rubiks_state_smaller2 = if rubiks_state.size() % 2 == 1 {rubiks_state.from_outer_to_smaller_cube_size(3)}
+            else {rubiks_state.from_outer_to_smaller_cube_size(4)};
+            if let Ok(turns) = self.solve_with_idastar(&rubiks_state_smaller2)
+            {
+                heuristics.push(turns.turns.len());
+            }
+        }
+
+        return Some(heuristics.iter().cloned().fold(heuristics[0], usize::max));
+    }
+
+    /// The most the heuristics can give for any state, or `None` if there is no known limit.
+    /// The corner table is a 2x2x2 so it gives at most [`gods_number`]`(2)`, other tables and custom heuristics have no limit.
+    ///
+    /// [`gods_number`]: fn.gods_number.html
+    fn max_heuristic(&self) -> Option<usize>
+    {
+        if self.custom_heuristic.is_some()
+        {
+            return None;
+        }
+
+        match &self.heuristic_table
+        {
+            Some(table) if table.has(TableKind::Edges) => None,
+            Some(table) if table.has(TableKind::Corners) => gods_number(2, Metric::Quarter),
+            _ => Some(0),
+        }
+    }
+
+    /// Builds every state of the 2x2x2 (held the same way, see [`HeuristicsTables::corner_table`]) with its distance from solved.
+    /// This is the same as building a new corner table, so it takes a while.
+    ///
+    /// [`HeuristicsTables::corner_table`]: struct.HeuristicsTables.html#method.corner_table
+    #[allow(dead_code)]
+    pub fn enumerate_group_2x2x2() -> HashMap<rubiks::CanonicalState, u8>
+    {
+        let mut ht = HeuristicsTables::new();
+        ht.calc_corner_heuristics_table();
+        ht.corners.expect("the corner table was just built")
+    }
+
+    /// Gives a lower bound on the number of quarter turns needed to solve `rubiks_state` using the heuristics tables.
+    /// For the 2x2x2 this is the exact distance.
+    pub fn estimated_distance(&self, rubiks_state: &rubiks::RubiksCubeState) -> Result<usize, RubikSolveError>
+    {
+        self.calc_heuristics(rubiks_state, false, None).ok_or(RubikSolveError::NoHeuristicsTable)
+    }
+
+    /// Rates `rubiks_state` with the default thresholds for its size, see [`DifficultyConfig::for_size`].
+    #[allow(dead_code)]
+    pub fn difficulty(&self, rubiks_state: &rubiks::RubiksCubeState) -> Result<Difficulty, RubikSolveError>
+    {
+        self.difficulty_with_config(rubiks_state, &DifficultyConfig::for_size(rubiks_state.size()))
+    }
+
+    /// Rates `rubiks_state` by classifying its [`estimated_distance`] with `config`.
+    ///
+    /// [`estimated_distance`]: struct.RubiksCubeSolver.html#method.estimated_distance
+    pub fn difficulty_with_config(&self, rubiks_state: &rubiks::RubiksCubeState, config: &DifficultyConfig) -> Result<Difficulty, RubikSolveError>
+    {
+        Ok(config.classify(self.estimated_distance(rubiks_state)?))
+    }
+
+    /// will use heuristics if available
+    /// `k` is capped at [`gods_number`] since no state needs more turns than that.
+    ///
+    /// [`gods_number`]: fn.gods_number.html
+    pub fn solve_dpll(&self, rubiks_state: &rubiks::RubiksCubeState, k: usize) -> Result<rubiks::Move, RubikSolveError>
+    {
+        let k = gods_number(rubiks_state.size(), Metric::Quarter).map_or(k, |max_k| k.min(max_k));
+
+        if rubiks_state.is_solved()
+        {
+            return Ok(rubiks::Move::empty());
+        }
+        else if k <= 0
+        {
+            return Err(RubikSolveError::Unsolveable);
+        }
+    
+        // if !valid
+        // {
+        //     return (false, None);
+        // }
+
+        // We do each turn on the one state and undo it when we back track, this way we never clone the state.
+        let mut mut_state = rubiks_state.clone();
+        let mut mut_move = rubiks::Move::empty();
+        let all_turns = rubiks_state.all_turns();
+
+        if self.dpll_search(&mut mut_state, &mut mut_move, &all_turns, k)?
+        {
+            Ok(mut_move)
+        }
+        else
+        {
+            Err(RubikSolveError::Unsolveable)
+        }
+    }
+
+    /// The recursive part of [`solve_dpll`]. `mut_state` is the start state with `mut_move` applied and is not solved.
+    /// Returns true if a solution was found, in which case `mut_move` is the solution.
+    /// Otherwise `mut_state` and `mut_move` are left how they were given, unless the search was cancelled.
+    ///
+    /// [`solve_dpll`]: struct.RubiksCubeSolver.html#method.solve_dpll
+    fn dpll_search(&self, mut_state: &mut rubiks::RubiksCubeState, mut_move: &mut rubiks::Move, all_turns: &[rubiks::Turn], k: usize)
+        -> Result<bool, RubikSolveError>
+    {
+        if self.is_cancelled()
+        {
+            return Err(RubikSolveError::Cancelled);
+        }
+
+        let i = mut_move.turns.len();
+
+        // TODO: update to use a general smaller cube, not just 2x2x2
+        // the heuristics can only prune when fewer turns are left than the most they can give
+        if i > 0 && mut_state.size() > 2 && self.max_heuristic().is_none_or(|max_h| k-i < max_h)
+        {
+            //if there are no heuristics, we can't do anything
+            if let Some(h_val) = self.calc_heuristics(mut_state, false, None)
+            {
+                if h_val > k-i
+                {
+                    // our lower bound is to high
+                    return Ok(false);
+                }
+            }
+        }
+
+        // reversed to keep the order of the old stack based search
+        for &turn_type in all_turns.iter().rev()
+        {
+            if !mut_move.is_next_turn_efficient(turn_type)
+            {
+                continue;
+            }
+
+            // do turn, add to history
+            mut_state.turn(turn_type);
+            mut_move.turns.push(turn_type);
+
+            // if i+1 >= k we just made kth move so we don't go deeper
+            if mut_state.is_solved() || (i + 1 < k && self.dpll_search(mut_state, mut_move, all_turns, k)?)
+            {
+                return Ok(true);
+            }
+
+            // undo turn
+            mut_move.turns.pop();
+            mut_state.turn(turn_type.invert());
+        }
+
+        Ok(false)
+    }
+
+    /// Gives every solution of `rubiks_state` with at most `k` turns, shortest first, as the search finds them.
+    /// Nothing is searched until `next` is called and the search stops when the iterator is dropped, so
+    /// `.take(3)` only searches until the third solution. Solutions never go through the solved cube before the end
+    /// and use the same turn ordering as [`solve_dpll`] so they are all different.
+    ///
+    /// [`solve_dpll`]: struct.RubiksCubeSolver.html#method.solve_dpll
+    #[allow(dead_code)]
+    pub fn solutions_iter<'a>(&'a self, rubiks_state: &rubiks::RubiksCubeState, k: usize) -> impl Iterator<Item = rubiks::Move> + 'a
+    {
+        SolutionsIter{
+            solver: self,
+            state: rubiks_state.clone(),
+            path: rubiks::Move::empty(),
+            next_index: vec![],
+            all_turns: rubiks_state.all_turns(),
+            depth_limit: 0,
+            k,
+            started: false,
+        }
+    }
+
+    /// Gives `count` random nxnxn states, with `n = size`, each with a solution. The scrambles come from `seed` so the
+    /// same call always gives the same states.
+    ///
+    /// The 2x2x2 is scrambled with 100 turns and the 3x3x3 with 8 (more takes too long to solve), and both are solved with
+    /// [`solve_with_idastar`], so the solutions are optimal. Bigger cubes can't be solved in a reasonable time, so they
+    /// are scrambled with 8 turns and the solution is the scramble inverted and [`simplified`], which can be longer than
+    /// needed. If the solver fails, like when there is no heuristics table, the inverted scramble is used as well.
+    /// The 1x1x1 has no turns, so it is always solved with an empty solution. Panics if `size` is 0, like
+    /// [`std_solved_nxnxn`].
+    ///
+    /// [`solve_with_idastar`]: struct.RubiksCubeSolver.html#method.solve_with_idastar
+    /// [`simplified`]: ../rubiks/struct.Move.html#method.simplify
+    /// [`std_solved_nxnxn`]: ../rubiks/struct.RubiksCubeState.html#method.std_solved_nxnxn
+    #[allow(dead_code)]
+    pub fn generate_dataset(&self, size: usize, count: usize, seed: u64) -> Vec<(rubiks::RubiksCubeState, rubiks::Move)>
+    {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        let scramble_len = match size { 1 => 0, 2 => 100, _ => 8 };
+
+        (0..count).map(|_|
+        {
+            let scramble = rubiks::Move::rnd_move_with_rng(size, scramble_len, &mut rng);
+            let state = rubiks::RubiksCubeState::std_solved_nxnxn(size).do_move_copy(&scramble);
+            let soln = if size <= 3 { self.solve_with_idastar(&state).ok() } else { None };
+
+            (state, soln.unwrap_or_else(|| scramble.invert().simplify(size)))
+        }).collect()
+    }
+
+    /// Runs each of `algos` on the same `scrambles` random scrambles of `scramble_len` turns for a nxnxn cube with `n = size`.
+    /// The scrambles come from `seed` so the same call always uses the same scrambles.
+    #[allow(dead_code)]
+    pub fn compare(&self, algos: &[SolveAlgo], size: usize, scrambles: usize, scramble_len: usize, seed: u64) -> Vec<BenchReport>
+    {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        let states: Vec<rubiks::RubiksCubeState> = (0..scrambles).map(|_|
+        {
+            let mut state = rubiks::RubiksCubeState::std_solved_nxnxn(size);
+            state.do_move(&rubiks::Move::rnd_move_with_rng(size, scramble_len, &mut rng));
+            state
+        }).collect();
+
+        algos.iter().map(|&algo|
+        {
+            let mut report = BenchReport{algo, solved: 0, failed: 0, total_turns: 0, total_time: Duration::from_secs(0), solution_lengths: vec![]};
+
+            for state in &states
+            {
+                let t0 = Instant::now();
+                let soln = match algo
+                {
+                    SolveAlgo::Dpll => self.solve_dpll(state, scramble_len),
+                    SolveAlgo::IdaStar => self.solve_with_idastar(state),
+                    SolveAlgo::Iddfs => self.solutions_iter(state, scramble_len).next().ok_or(RubikSolveError::Unsolveable),
+                };
+                report.total_time += t0.elapsed();
+
+                match soln
+                {
+                    Ok(soln) if Self::verify_solution(state, &soln) =>
+                    {
+                        report.solved += 1;
+                        report.total_turns += soln.turns.len();
+                        report.solution_lengths.push(soln.turns.len());
+                    },
+                    _ => report.failed += 1,
+                }
+            }
+
+            report
+        }).collect()
+    }
+
+    /// The number of solutions of each length, in quarter turns, over `scrambles` random states of a nxnxn cube with
+    /// `n = size`. The states come from 100 random turns from `seed`, so the same call always gives the same histogram.
+    /// The 2x2x2 is solved with [`solve_with_idastar`], which is optimal, so this is the distribution of optimal
+    /// solution lengths. The 3x3x3 is solved with [`solve_thistlethwaite`], as solving random states optimally takes too
+    /// long. Other sizes have no solver for random states and give an empty histogram, as do failed solves.
+    ///
+    /// [`solve_with_idastar`]: struct.RubiksCubeSolver.html#method.solve_with_idastar
+    /// [`solve_thistlethwaite`]: struct.RubiksCubeSolver.html#method.solve_thistlethwaite
+    #[allow(dead_code)]
+    pub fn length_histogram(&self, size: usize, scrambles: usize, seed: u64) -> BTreeMap<usize, usize>
+    {
+        let mut histogram = BTreeMap::new();
+        if size != 2 && size != 3
+        {
+            return histogram;
+        }
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        for _ in 0..scrambles
+        {
+            let state = rubiks::RubiksCubeState::std_solved_nxnxn(size).do_move_copy(&rubiks::Move::rnd_move_with_rng(size, 100, &mut rng));
+            let soln = if size == 2 { self.solve_with_idastar(&state) } else { self.solve_thistlethwaite(&state) };
+
+            if let Some(soln) = soln.ok().filter(|soln| Self::verify_solution(&state, soln))
+            {
+                *histogram.entry(soln.turns.len()).or_insert(0) += 1;
+            }
+        }
+
+        histogram
+    }
+
+    /// Checks that doing `solution` on `rubiks_state` gives a solved cube.
+    #[allow(dead_code)]
+    pub fn verify_solution(rubiks_state: &rubiks::RubiksCubeState, solution: &rubiks::Move) -> bool
+    {
+        rubiks_state.do_move_copy(solution).is_solved()
+    }
+
+    /// Solves `rubiks_state` with [`solve_with_idastar`] and does the solution on it, giving the solution and the solved
+    /// cube. If the solution doesn't solve the cube this gives `WrongSolution` instead of the wrong answer.
+    ///
+    /// [`solve_with_idastar`]: struct.RubiksCubeSolver.html#method.solve_with_idastar
+    #[allow(dead_code)]
+    pub fn solve_verified(&self, rubiks_state: &rubiks::RubiksCubeState) -> Result<(rubiks::Move, rubiks::RubiksCubeState), RubikSolveError>
+    {
+        let soln = self.solve_with_idastar(rubiks_state)?;
+        let solved = rubiks_state.do_move_copy(&soln);
+        if !solved.is_solved()
+        {
+            return Err(RubikSolveError::WrongSolution);
+        }
+
+        Ok((soln, solved))
+    }
+
+    fn get_heuristic_from_table_or_calc(&self, this_heuristics_table: &mut Option<HashMap<rubiks::RubiksCubeState, usize>>,
+        state: &rubiks::RubiksCubeState, g: usize, solve_smaller: bool, bound: Option<usize>)
+        -> Option<usize>
+    {
+        if g < 7  // todo calc from cube size
+        {
+            if let Some(this_table) = this_heuristics_table.as_mut()
+            {
+                if let Some(&val_in_table) = this_table.get(&state)
+                {
+                    Some(val_in_table)
+                }
+                else
+                {
+                    let val = self.calc_heuristics(state, solve_smaller, bound);
+                    if let Some(num) = val
+                    {
+                        this_table.insert(state.clone(), num);
+                    }
+                    val
+                }
+            }
+            else
+            {
+                self.calc_heuristics(state, solve_smaller, bound)
+            }
+        }
+        else
+        {
+            self.calc_heuristics(state, solve_smaller, bound)
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn solve_with_idastar(&self, rubiks_state: &rubiks::RubiksCubeState) -> Result<rubiks::Move, RubikSolveError>
+    {
+        self.idastar(rubiks_state, true, None, None).map(|(soln, _)| soln)
+    }
+
+    /// Same as [`solve_with_idastar`] but only looks for solutions of at most `max_moves` turns. If there isn't one,
+    /// this gives `BestEffort` with the turns to the state the search found with the lowest heuristic, so the best it
+    /// could do within `max_moves`. Because the search stops once the bound goes past `max_moves`, this doesn't wait on
+    /// long solutions like [`solve_with_idastar`] does.
+    ///
+    /// [`solve_with_idastar`]: struct.RubiksCubeSolver.html#method.solve_with_idastar
+    #[allow(dead_code)]
+    pub fn solve_capped(&self, rubiks_state: &rubiks::RubiksCubeState, max_moves: usize) -> Result<rubiks::Move, RubikSolveError>
+    {
+        self.idastar(rubiks_state, true, Some(max_moves), None).map(|(soln, _)| soln)
+    }
+
+    /// Same as [`solve_capped`] with `k` as the most turns, but gives `None` for any error instead of saying why.
+    /// For callers that only care whether there is a solution within `k` turns.
+    ///
+    /// [`solve_capped`]: struct.RubiksCubeSolver.html#method.solve_capped
+    #[allow(dead_code)]
+    pub fn solve_opt(&self, rubiks_state: &rubiks::RubiksCubeState, k: usize) -> Option<rubiks::Move>
+    {
+        self.solve_capped(rubiks_state, k).ok()
+    }
+
+    /// Same as [`solve_dpll`] but gives `None` for any error instead of saying why.
+    ///
+    /// [`solve_dpll`]: struct.RubiksCubeSolver.html#method.solve_dpll
+    #[allow(dead_code)]
+    pub fn solve_dpll_opt(&self, rubiks_state: &rubiks::RubiksCubeState, k: usize) -> Option<rubiks::Move>
+    {
+        self.solve_dpll(rubiks_state, k).ok()
+    }
+
+    /// The number of quarter turns in an optimal solution, without the turns. For the 2x2x2 this is a lookup in the corner
+    /// table (see [`HeuristicsTables::exact_2x2_distance`]), `Unsolveable` if the state isn't in it. Other sizes are solved
+    /// with [`solve_with_idastar`], which is optimal, so this takes as long as solving.
+    ///
+    /// [`HeuristicsTables::exact_2x2_distance`]: struct.HeuristicsTables.html#method.exact_2x2_distance
+    /// [`solve_with_idastar`]: struct.RubiksCubeSolver.html#method.solve_with_idastar
+    #[allow(dead_code)]
+    pub fn optimal_distance(&self, rubiks_state: &rubiks::RubiksCubeState) -> Result<usize, RubikSolveError>
+    {
+        if let Some(corner_table) = self.heuristic_table.as_ref().filter(|table| table.corners.is_some() && rubiks_state.size() == 2)
+        {
+            return corner_table.exact_2x2_distance(rubiks_state).map(|d| d as usize).ok_or(RubikSolveError::Unsolveable);
+        }
+
+        self.solve_with_idastar(rubiks_state).map(|soln| soln.turns.len())
+    }
+
+    /// Solves the cube you get by doing `scramble` on a solved cube. The solution is found with [`solve_with_idastar`]
+    /// so it is never longer than `scramble` and is often shorter than just inverting it.
+    /// Gives `BadInput` if `scramble` is empty (we don't know the cube size) or has turns for different cube sizes.
+    ///
+    /// [`solve_with_idastar`]: struct.RubiksCubeSolver.html#method.solve_with_idastar
+    #[allow(dead_code)]
+    pub fn solve_from_scramble(&self, scramble: &rubiks::Move) -> Result<rubiks::Move, RubikSolveError>
+    {
+        let sizes: Vec<usize> = scramble.turns.iter().map(|turn| turn.cube_size()).collect();
+
+        let n = *sizes.first().ok_or(RubikSolveError::BadInput)?;
+        if sizes.iter().any(|&size| size != n)
+        {
+            return Err(RubikSolveError::BadInput);
+        }
+
+        let mut state = rubiks::RubiksCubeState::std_solved_nxnxn(n);
+        state.do_move(scramble);
+        self.solve_with_idastar(&state)
+    }
+
+    /// Finds a move that takes `from` to `to`. Both states are solved with [`solve_with_idastar`], if either needs more than `k`
+    /// turns we give `Unsolveable`. The move is the solution for `from` then the inverse of the solution for `to`, with
+    /// whole cube rotations in between for even cubes (they can be solved in any orientation).
+    /// Gives `BadInput` if the cubes are different sizes and `Unsolveable` if `to` can't be reached from `from`.
+    ///
+    /// [`solve_with_idastar`]: struct.RubiksCubeSolver.html#method.solve_with_idastar
+    pub fn solve_to(&self, from: &rubiks::RubiksCubeState, to: &rubiks::RubiksCubeState, k: usize) -> Result<rubiks::Move, RubikSolveError>
+    {
+        if from.size() != to.size()
+        {
+            return Err(RubikSolveError::BadInput);
+        }
+
+        let from_soln = self.solve_with_idastar(from)?;
+        let to_soln = self.solve_with_idastar(to)?;
+        if from_soln.turns.len() > k || to_soln.turns.len() > k
+        {
+            return Err(RubikSolveError::Unsolveable);
+        }
+
+        let from_solved = from.do_move_copy(&from_soln);
+        let to_solved = to.do_move_copy(&to_soln);
+
+        let rotation = Self::rotation_between(&from_solved, &to_solved).ok_or(RubikSolveError::Unsolveable)?;
+
+        Ok(from_soln * rotation * to_soln.invert())
+    }
+
+    /// Finds whole cube rotations, made of turns of every layer, that take `from` to `to`. Only even cubes can be
+    /// rotated like this, odd cubes only match if they are already the same.
+    fn rotation_between(from: &rubiks::RubiksCubeState, to: &rubiks::RubiksCubeState) -> Option<rubiks::Move>
+    {
+        let rotations: Vec<rubiks::Move> = [rubiks::Axis::X, rubiks::Axis::Y, rubiks::Axis::Z].iter()
+            .filter_map(|&axis| rubiks::Move::rotation(axis, from.size())).collect();
+
+        // there are only 24 ways to hold the cube so a bfs is fine
+        let mut vq: VecDeque<(rubiks::RubiksCubeState, rubiks::Move)> = VecDeque::new();
+        let mut seen: Vec<rubiks::RubiksCubeState> = vec![];
+        vq.push_back((from.clone(), rubiks::Move::empty()));
+
+        while let Some((state, rotation_move)) = vq.pop_front()
+        {
+            if state == *to
+            {
+                return Some(rotation_move);
+            }
+            if seen.contains(&state)
+            {
+                continue;
+            }
+
+            for rotation in &rotations
+            {
+                let next_state = state.do_move_copy(rotation);
+                vq.push_back((next_state, rotation_move.clone() * rotation.clone()));
+            }
+            seen.push(state);
+        }
+
+        None
+    }
+
+    /// Solves `rubiks_state` using only the moves in `generators`, like `U2` and `R2` to stay in the `<U2, R2>` subgroup.
+    /// The solution is the generator moves one after another and uses the fewest generators, not turns.
+    /// This is a bfs over every state the generators reach, so it is for generators that make a small subgroup.
+    /// Gives `Unsolveable` if it needs more than `k` generators or the state isn't in the subgroup (found once all of the
+    /// subgroup within `k` is searched), and `BadInput` if a generator has turns for a different cube size.
+    #[allow(dead_code)]
+    pub fn solve_with_generators(&self, rubiks_state: &rubiks::RubiksCubeState, generators: &[rubiks::Move], k: usize)
+        -> Result<rubiks::Move, RubikSolveError>
+    {
+        if generators.iter().flat_map(|generator| generator.turns.iter()).any(|turn| turn.cube_size() != rubiks_state.size())
+        {
+            return Err(RubikSolveError::BadInput);
+        }
+
+        let mut vq: VecDeque<(rubiks::RubiksCubeState, Vec<usize>)> = VecDeque::new();
+        let mut seen: HashSet<rubiks::RubiksCubeState> = HashSet::new();
+        vq.push_back((rubiks_state.clone(), vec![]));
+        seen.insert(rubiks_state.clone());
+
+        while let Some((state, path)) = vq.pop_front()
+        {
+            if self.is_cancelled()
+            {
+                return Err(RubikSolveError::Cancelled);
+            }
+
+            if state.is_solved()
+            {
+                let moves: Vec<rubiks::Move> = path.iter().map(|&i| generators[i].clone()).collect();
+                return Ok(rubiks::Move::concat(&moves));
+            }
+            if path.len() >= k
+            {
+                continue;
+            }
+
+            for (i, generator) in generators.iter().enumerate()
+            {
+                let next_state = state.do_move_copy(generator);
+                if seen.insert(next_state.clone())
+                {
+                    let mut next_path = path.clone();
+                    next_path.push(i);
+                    vq.push_back((next_state, next_path));
+                }
+            }
+        }
+
+        Err(RubikSolveError::Unsolveable)
+    }
+
+    /// Solves `rubiks_state` one phase at a time, like with [`ThistlethwaitePhase`]. Each phase is searched with IDA* from
+    /// where the phase before it ended, using the phase's moves and heuristic, and the moves of all the phases are put
+    /// together. Each phase's part is as short as it can be, counting every one of its moves as one step, but the whole
+    /// solution usually isn't. The cube is only solved at the end if the goal of the last phase is solved.
+    ///
+    /// Moves that go back to a state already on the path are skipped, but a phase whose goal can't be reached keeps
+    /// searching, see [`set_cancel_flag`]. Gives `Unsolveable` if a phase runs out of moves to try.
+    ///
+    /// [`ThistlethwaitePhase`]: enum.ThistlethwaitePhase.html
+    /// [`set_cancel_flag`]: struct.RubiksCubeSolver.html#method.set_cancel_flag
+    #[allow(dead_code)]
+    pub fn solve_phases(&self, rubiks_state: &rubiks::RubiksCubeState, phases: &[Box<dyn PhaseSolver>]) -> Result<rubiks::Move, RubikSolveError>
+    {
+        let mut state = rubiks_state.clone();
+        let mut soln = rubiks::Move::empty();
+
+        for phase in phases
+        {
+            let phase_soln = self.solve_phase(&state, phase.as_ref())?;
+            state.do_move(&phase_soln);
+            soln *= phase_soln;
+        }
+
+        Ok(soln)
+    }
+
+    /// Solves a 3x3x3 with Thistlethwaite's algorithm, the four [`ThistlethwaitePhase`]s with [`solve_phases`].
+    /// The solutions are far from optimal, at most 45 half turns and usually around 30, but even the hardest states are
+    /// solved quickly, unlike with [`solve_with_idastar`]. The first solve also builds the tables for the phases.
+    /// Gives `BadInput` if the cube isn't a 3x3x3 and `Unsolveable` if the stickers can't come from a solved cube
+    /// (see [`rubiks::RubiksCubeState::parity_signature`]).
+    ///
+    /// [`ThistlethwaitePhase`]: enum.ThistlethwaitePhase.html
+    /// [`solve_phases`]: struct.RubiksCubeSolver.html#method.solve_phases
+    /// [`solve_with_idastar`]: struct.RubiksCubeSolver.html#method.solve_with_idastar
+    /// [`rubiks::RubiksCubeState::parity_signature`]: ../rubiks/struct.RubiksCubeState.html#method.parity_signature
+    #[allow(dead_code)]
+    pub fn solve_thistlethwaite(&self, rubiks_state: &rubiks::RubiksCubeState) -> Result<rubiks::Move, RubikSolveError>
+    {
+        if rubiks_state.size() != 3
+        {
+            return Err(RubikSolveError::BadInput);
+        }
+        if rubiks_state.parity_signature() != Some((0, 0, 0))
+        {
+            return Err(RubikSolveError::Unsolveable);
+        }
+
+        let phases: Vec<Box<dyn PhaseSolver>> = ThistlethwaitePhase::all().iter()
+            .map(|&phase| Box::new(phase) as Box<dyn PhaseSolver>).collect();
+        self.solve_phases(rubiks_state, &phases)
+    }
+
+    /// The most turns [`solve_with_fallback`] lets the optimal solver look for on cubes bigger than the 2x2x2, past this
+    /// the search gets too slow to be worth waiting on.
+    ///
+    /// [`solve_with_fallback`]: struct.RubiksCubeSolver.html#method.solve_with_fallback
+    pub const FALLBACK_OPTIMAL_MAX_TURNS: usize = 8;
+
+    /// Tries the solvers from best to most robust until one works and says which one it was, for when something has to
+    /// be given back. First an optimal solution with [`solve_capped`], up to God's number for the 2x2x2 and
+    /// [`FALLBACK_OPTIMAL_MAX_TURNS`] for bigger cubes (it needs the heuristics table), then [`solve_thistlethwaite`]
+    /// for the 3x3x3, in place of Kociemba's algorithm which isn't written yet. There is no solver yet for random states of
+    /// bigger cubes (like a reduction to a 3x3x3), so those only get the optimal try. Every solution is checked with
+    /// [`verify_solution`] before it is given back.
+    ///
+    /// Gives `Cancelled` with `SolverUsed::Nothing` right away if the cancel flag is set, see [`set_cancel_flag`].
+    ///
+    /// [`solve_capped`]: struct.RubiksCubeSolver.html#method.solve_capped
+    /// [`FALLBACK_OPTIMAL_MAX_TURNS`]: struct.RubiksCubeSolver.html#associatedconstant.FALLBACK_OPTIMAL_MAX_TURNS
+    /// [`solve_thistlethwaite`]: struct.RubiksCubeSolver.html#method.solve_thistlethwaite
+    /// [`verify_solution`]: struct.RubiksCubeSolver.html#method.verify_solution
+    /// [`set_cancel_flag`]: struct.RubiksCubeSolver.html#method.set_cancel_flag
+    #[allow(dead_code)]
+    pub fn solve_with_fallback(&self, rubiks_state: &rubiks::RubiksCubeState) -> (Result<rubiks::Move, RubikSolveError>, SolverUsed)
+    {
+        let max_turns = if rubiks_state.size() <= 2 { gods_number(2, Metric::Quarter).unwrap() } else { Self::FALLBACK_OPTIMAL_MAX_TURNS };
+        // TODO: try Kociemba's algorithm before Thistlethwaite and a reduction for bigger cubes once there are solvers for them
+        let mut attempts = vec![FallbackAttempt::Optimal];
+        if rubiks_state.size() == 3
+        {
+            attempts.push(FallbackAttempt::Thistlethwaite);
+        }
+
+        let mut last_err = RubikSolveError::BadInput;
+        for &attempt in &attempts
+        {
+            let soln = match attempt
+            {
+                FallbackAttempt::Optimal => self.solve_capped(rubiks_state, max_turns),
+                FallbackAttempt::Thistlethwaite => self.solve_thistlethwaite(rubiks_state),
+            };
+
+            match soln
+            {
+                Ok(soln) if Self::verify_solution(rubiks_state, &soln) => return (Ok(soln), attempt.used()),
+                Ok(_) => last_err = RubikSolveError::WrongSolution,
+                Err(RubikSolveError::Cancelled) => return (Err(RubikSolveError::Cancelled), SolverUsed::Nothing),
+                Err(err) => last_err = err,
+            }
+        }
+
+        (Err(last_err), SolverUsed::Nothing)
+    }
+
+    /// The IDA* for one phase of [`solve_phases`].
+    ///
+    /// [`solve_phases`]: struct.RubiksCubeSolver.html#method.solve_phases
+    fn solve_phase(&self, rubiks_state: &rubiks::RubiksCubeState, phase: &dyn PhaseSolver) -> Result<rubiks::Move, RubikSolveError>
+    {
+        let mut bound = phase.heuristic(rubiks_state);
+        let mut path = vec![rubiks_state.clone()];
+        let mut moves = vec![];
+
+        loop
+        {
+            let mut min_f = None;
+            if self.phase_search(phase, &mut path, &mut moves, bound, &mut min_f)?
+            {
+                return Ok(rubiks::Move::concat(&moves));
+            }
+
+            bound = min_f.ok_or(RubikSolveError::Unsolveable)?;
+        }
+    }
+
+    /// The recursive part of [`solve_phase`] for one `bound`, from the last state of `path` with `moves` done so far.
+    /// Works like [`idastar_search`], `min_f` is set to the smallest `f` that went over the `bound`.
+    ///
+    /// [`solve_phase`]: struct.RubiksCubeSolver.html#method.solve_phase
+    /// [`idastar_search`]: struct.RubiksCubeSolver.html#method.idastar_search
+    fn phase_search(&self, phase: &dyn PhaseSolver, path: &mut Vec<rubiks::RubiksCubeState>, moves: &mut Vec<rubiks::Move>,
+        bound: usize, min_f: &mut Option<usize>) -> Result<bool, RubikSolveError>
+    {
+        if self.is_cancelled()
+        {
+            return Err(RubikSolveError::Cancelled);
+        }
+
+        let state = path.last().unwrap();
+        if phase.goal(state)
+        {
+            return Ok(true);
+        }
+
+        let next_g = moves.len() + 1;
+        let mut next_moves = vec![];
+        for phase_move in phase.moves(state)
+        {
+            let next_state = state.do_move_copy(&phase_move);
+            if path.contains(&next_state)
+            {
+                continue;
+            }
+
+            let next_f = next_g + phase.heuristic(&next_state);
+            if next_f > bound
+            {
+                *min_f = Some(min_f.map_or(next_f, |min_f| min_f.min(next_f)));
+            }
+            else
+            {
+                next_moves.push((next_f, phase_move, next_state));
+            }
+        }
+
+        // try the most promising moves first
+        next_moves.sort_by_key(|a| a.0);
+
+        for (_, phase_move, next_state) in next_moves
+        {
+            path.push(next_state);
+            moves.push(phase_move);
+
+            if self.phase_search(phase, path, moves, bound, min_f)?
+            {
+                return Ok(true);
+            }
+
+            moves.pop();
+            path.pop();
+        }
+
+        Ok(false)
+    }
+
+    /// Same as [`solve_with_idastar`] but also gives the [`SolveStats`] of the search.
+    ///
+    /// [`solve_with_idastar`]: struct.RubiksCubeSolver.html#method.solve_with_idastar
+    /// [`SolveStats`]: struct.SolveStats.html
+    #[allow(dead_code)]
+    pub fn solve_with_idastar_stats(&self, rubiks_state: &rubiks::RubiksCubeState) -> Result<(rubiks::Move, SolveStats), RubikSolveError>
+    {
+        self.idastar(rubiks_state, true, None, None)
+    }
+
+    /// Same as [`solve_with_idastar_stats`] but calls `trace_sink` for every node the search goes to, with its depth, the
+    /// turn that got there and its `f` (the depth plus the heuristic), for drawing the search tree. Each bound starts
+    /// again from the cube given, which is depth 0 with no turn, and the nodes come in the order they are searched, so a
+    /// node's parent is the last node before it that is one less deep. There is one call for each of the `nodes` in
+    /// the [`SolveStats`].
+    ///
+    /// [`solve_with_idastar_stats`]: struct.RubiksCubeSolver.html#method.solve_with_idastar_stats
+    /// [`SolveStats`]: struct.SolveStats.html
+    #[allow(dead_code)]
+    pub fn solve_with_idastar_observed(&self, rubiks_state: &rubiks::RubiksCubeState,
+        trace_sink: &mut dyn FnMut(usize, Option<rubiks::Turn>, usize)) -> Result<(rubiks::Move, SolveStats), RubikSolveError>
+    {
+        self.idastar(rubiks_state, true, None, Some(trace_sink))
+    }
+
+    /// Same as [`solve_with_idastar`] but also gives a [`MoveTrace`] for each turn of the solution, with the heuristic
+    /// before and after the turn, so you can show that a turn took the estimated distance from 8 to 7.
+    /// On an optimal solution each turn takes the estimate down by at most 1 and it ends at 0.
+    ///
+    /// [`solve_with_idastar`]: struct.RubiksCubeSolver.html#method.solve_with_idastar
+    /// [`MoveTrace`]: struct.MoveTrace.html
+    #[allow(dead_code)]
+    pub fn solve_with_idastar_traced(&self, rubiks_state: &rubiks::RubiksCubeState) -> Result<(rubiks::Move, Vec<MoveTrace>), RubikSolveError>
+    {
+        let soln = self.solve_with_idastar(rubiks_state)?;
+
+        let mut state = rubiks_state.clone();
+        let mut heuristic = self.calc_heuristics(&state, true, None);
+        let mut trace = Vec::with_capacity(soln.turns.len());
+        for &turn in &soln.turns
+        {
+            state.turn(turn);
+            let heuristic_after = self.calc_heuristics(&state, true, None);
+            trace.push(MoveTrace{turn, heuristic_before: heuristic, heuristic_after});
+            heuristic = heuristic_after;
+        }
+
+        Ok((soln, trace))
+    }
+
+    /// If `detect_cycles` is set, turns that go back to a state on the current path (up to how the cube is held) are skipped.
+    /// An optimal solution never visits a state twice so this doesn't change the solution length. It costs one
+    /// rotation-normalized copy of the state per turn in the current path, so `O(depth * 6n^2)` bytes, and a scan of the
+    /// path for every turn within the bound.
+    ///
+    /// If there is a `max_moves` the search stops once the bound goes past it and gives `BestEffort`, see [`solve_capped`].
+    /// If there is a `trace_sink` it is called for every node, see [`solve_with_idastar_observed`].
+    ///
+    /// [`solve_capped`]: struct.RubiksCubeSolver.html#method.solve_capped
+    /// [`solve_with_idastar_observed`]: struct.RubiksCubeSolver.html#method.solve_with_idastar_observed
+    fn idastar(&self, rubiks_state: &rubiks::RubiksCubeState, detect_cycles: bool, max_moves: Option<usize>,
+        trace_sink: Option<&mut dyn FnMut(usize, Option<rubiks::Turn>, usize)>) -> Result<(rubiks::Move, SolveStats), RubikSolveError>
+    {
+        let mut this_heuristics_table: Option<HashMap<rubiks::RubiksCubeState, usize>> = if rubiks_state.size() > 4
+        {
+            // if the size is greater than we use more than just the basic corner heuristics
+            // grows as states are seen instead of starting with room for millions of them
+            Some(HashMap::new()) // TODO: should we use usize or something smaller
+        }
+        else
+        {
+            None
+        };
+    
+        // ida star that uses smaller cubes as the heuristic
+        let start_h = self.get_heuristic_from_table_or_calc(&mut this_heuristics_table, rubiks_state, 0, true, None)
+                                .ok_or(RubikSolveError::NoHeuristicsTable)?;
+        let mut bound = start_h;
+        // println!("new bound: {}", bound);
+
+        // Like with dpll, we do and undo the turns on the one state instead of cloning it for every node.
+        let mut search = IdaStarSearch{
+            state: rubiks_state.clone(),
+            rubiks_move: rubiks::Move::empty(),
+            all_turns: rubiks_state.all_turns(),
+            heuristics_table: this_heuristics_table,
+            path: if detect_cycles { Some(vec![rubiks_state.normalized_rotation()]) } else { None },
+            stats: SolveStats::default(),
+            best: max_moves.map(|max_moves| CappedBest{max_moves, heuristic: start_h, turns: rubiks::Move::empty()}),
+            trace_sink,
+        };
+
+        loop
+        {
+            let mut min_turns: Option<usize> = None;
+            // past the cap we do one last search at the cap, so there is a best effort even if the first bound is over it
+            let over_cap = max_moves.filter(|&max_moves| bound > max_moves);
+
+            if let Some(trace_sink) = search.trace_sink.as_mut()
+            {
+                trace_sink(0, None, start_h);
+            }
+            if self.idastar_search(&mut search, over_cap.unwrap_or(bound), &mut min_turns)?
+            {
+                return Ok((search.rubiks_move, search.stats));
+            }
+
+            if over_cap.is_some()
+            {
+                if let Some(best) = search.best.take()
+                {
+                    return Err(RubikSolveError::BestEffort(best.turns));
+                }
+            }
+
+            if let Some(num_min_turns) = min_turns
+            {
+                bound = num_min_turns;
+                // println!("new bound: {}", bound);
+            }
+            else
+            {
+                return Err(RubikSolveError::Unsolveable)
+            }
+        }
+    }
+
+    /// The recursive part of [`solve_with_idastar`] for one `bound`. Works like [`dpll_search`] where the state and turns
+    /// of the `search` are left how they were given unless a solution is found.
+    /// `min_turns` is set to the smallest `f` that went over the `bound`, this is the bound for the next iteration.
+    ///
+    /// [`solve_with_idastar`]: struct.RubiksCubeSolver.html#method.solve_with_idastar
+    /// [`dpll_search`]: struct.RubiksCubeSolver.html#method.dpll_search
+    fn idastar_search(&self, search: &mut IdaStarSearch, bound: usize, min_turns: &mut Option<usize>) -> Result<bool, RubikSolveError>
+    {
+        if self.is_cancelled()
+        {
+            return Err(RubikSolveError::Cancelled);
+        }
+
+        search.stats.nodes += 1;
+
+        if search.state.is_solved()
+        {
+            return Ok(true);
+        }
+
+        let next_g = search.rubiks_move.turns.len() + 1;
+        let mut next_turns: Vec<(usize, rubiks::Turn, Option<rubiks::RubiksCubeState>)> = vec![];
+
+        for turn_type in search.rubiks_move.allowed_next_turns(&search.all_turns)
+        {
+            search.state.turn(turn_type);
+            let next_h = self.get_heuristic_from_table_or_calc(&mut search.heuristics_table, &search.state, next_g, true, min_turns.map(|val| val - next_g))
+                .ok_or(RubikSolveError::NoHeuristicsTable)?;
+            let next_f = next_g + next_h;
+            if let Some(best) = search.best.as_mut()
+            {
+                if next_h < best.heuristic && next_g <= best.max_moves
+                {
+                    best.heuristic = next_h;
+                    best.turns = search.rubiks_move.clone() * turn_type.as_move();
+                }
+            }
+            let next_state = match &search.path
+            {
+                Some(path) if next_f <= bound =>
+                {
+                    let next_state = search.state.normalized_rotation();
+                    if path.contains(&next_state)
+                    {
+                        search.state.turn(turn_type.invert());
+                        search.stats.cycles_pruned += 1;
+                        continue;
+                    }
+                    Some(next_state)
+                },
+                _ => None
+            };
+            search.state.turn(turn_type.invert());
+
+            if next_f > bound
+            {
+                if let Some(num_min_turns) = *min_turns
+                {
+                    if next_f < num_min_turns
+                    {
+                        *min_turns = Some(next_f)
+                    }
+                }
+                else
+                {
+                    *min_turns = Some(next_f)
+                }
+            }
+            else
+            {
+                next_turns.push((next_f, turn_type, next_state));
+            }
+        }
+
+        // try the most promising turns first
+        next_turns.sort_by_key(|a| a.0);
+
+        for (next_f, turn_type, next_state) in next_turns
+        {
+            if let Some(trace_sink) = search.trace_sink.as_mut()
+            {
+                trace_sink(next_g, Some(turn_type), next_f);
+            }
+            search.state.turn(turn_type);
+            search.rubiks_move.turns.push(turn_type);
+            if let (Some(path), Some(next_state)) = (search.path.as_mut(), next_state)
+            {
+                path.push(next_state);
+            }
+
+            if self.idastar_search(search, bound, min_turns)?
+            {
+                return Ok(true);
+            }
+
+            if let Some(path) = search.path.as_mut()
+            {
+                path.pop();
+            }
+            search.rubiks_move.turns.pop();
+            search.state.turn(turn_type.invert());
+        }
+
+        Ok(false)
+    }
+
+    #[allow(dead_code)]
+    pub fn solve_best_approximation(&self, rubiks_state: &rubiks::RubiksCubeState) -> Result<rubiks::Move, RubikSolveError>
+    {
+        todo!()
+    }
+}
+
+// #[test]
+// fn test_calc_heuristics_table()
+// {
+//     assert!(false);
+//     let (r_state, _scram_move) = rubiks::RubiksCubeState::rnd_scramble(2, 1000);
+//
+//     let mut solver = RubiksCubeSolver::from_state(r_state.clone());
+//     solver.calc_heuristics_table();
+//
+//     //println!("moves away: {:?}", solver.calc_heuristics(&r_state, 14));
+//     assert!(solver.calc_heuristics(&r_state, 14).unwrap() <= 14);
+//
+//     for _ in 0..100
+//     {
+//         let (r_state2, _scram_move) = rubiks::RubiksCubeState::rnd_scramble(2, 1000);
+//         let num = solver.calc_heuristics(&r_state2, 14).unwrap();
+//         //println!("moves away: {}", num);
+//         assert!(num <= 14);
+//
+//         if num > 1
+//         {
+//             assert_eq!(solver.calc_heuristics(&r_state2, num-1), None)
+//         }
+//     }
+// }
+//
+// #[test]
+// fn encode_bit_strings()
+// {
+//     let n = 5;
+//     let m = 3;
+//     let s = 6*n+2*m;
+//
+//     let ls: Vec<[u8; 3]> = vec![[0, 1, 1], [1, 1, 0], [1, 1, 1], [1, 0, 0], [0, 0, 0]];
+//
+//     // let n = 3;
+//     // let m = 2;
+//     // let s = 6*n+2*m;
+//
+//     // let ls: Vec<[u8; 2]> = vec![[1, 1], [0, 1], [0, 0]];
+//
+//     let bs: Vec<rubiks::Move> = ls.clone().into_iter().enumerate().map(|(i,l)| 
+//     {
+//         let mut a_i = rubiks::Move::empty();
+//         for (j, bit) in l.iter().enumerate()
+//         {
+//             if *bit != 0 
+//             { 
+//                 a_i *= rubiks::Turn::AxisBased{
+//                     axis: rubiks::Axis::X, pos_rot: true, index: (j+1) as isize, cube_size: s}.as_move();
+//             }
+//         }
+//         let z_m_i = rubiks::Turn::AxisBased{
+//                     axis: rubiks::Axis::Z, pos_rot: true, index: (m+i+1) as isize, cube_size: s}.as_move();
+//
+//         a_i.clone() * z_m_i * a_i.invert()
+//     }).collect();
+//
+//     let mut state = rubiks::RubiksCubeState::std_solved_nxnxn(s);
+//
+//     let mut a_1 = rubiks::Move::empty();
 //     for (j, bit) in ls[0].iter().enumerate()
 //     {
 //         if *bit != 0 
@@ -561,3 +2345,928 @@ impl RubiksCubeSolver
 //         }
 //     }
 // }
+
+#[test]
+fn test_difficulty_config()
+{
+    let config = DifficultyConfig::for_size(3);
+    assert_eq!(config.classify(0), Difficulty::Easy);
+    assert_eq!(config.classify(config.easy_max), Difficulty::Easy);
+    assert_eq!(config.classify(config.easy_max + 1), Difficulty::Medium);
+    assert_eq!(config.classify(config.medium_max), Difficulty::Medium);
+    assert_eq!(config.classify(config.medium_max + 1), Difficulty::Hard);
+
+    let custom = DifficultyConfig{easy_max: 1, medium_max: 2};
+    assert_eq!(custom.classify(2), Difficulty::Medium);
+    assert_eq!(custom.classify(3), Difficulty::Hard);
+
+    // no tables means no estimate
+    let solver = RubiksCubeSolver::new();
+    let (state, _) = rubiks::RubiksCubeState::rnd_scramble(3, 10);
+    assert!(matches!(solver.difficulty(&state), Err(RubikSolveError::NoHeuristicsTable)));
+}
+
+#[test]
+fn test_solve_dpll_undo()
+{
+    // no heuristics table, so this is a plain depth first search over the do/undo turns
+    let solver = RubiksCubeSolver::new();
+
+    for n in 2..=4
+    {
+        for _ in 0..5
+        {
+            let (state, scram_move) = rubiks::RubiksCubeState::rnd_scramble(n, 3);
+            let state_before = state.clone();
+
+            let soln = solver.solve_dpll(&state, 3).unwrap();
+            assert!(soln.turns.len() <= 3);
+            assert!(RubiksCubeSolver::verify_solution(&state, &soln));
+            assert!(RubiksCubeSolver::verify_solution(&state, &scram_move.invert()));
+
+            // the given state is not changed
+            assert_eq!(state, state_before);
+        }
+    }
+
+    let (state, _) = rubiks::RubiksCubeState::rnd_scramble(3, 100);
+    assert!(!RubiksCubeSolver::verify_solution(&state, &rubiks::Move::empty()));
+}
+
+#[test]
+fn test_custom_heuristic()
+{
+    let mut solver = RubiksCubeSolver::new();
+    let (state, _) = rubiks::RubiksCubeState::rnd_scramble(3, 3);
+    assert!(matches!(solver.solve_with_idastar(&state), Err(RubikSolveError::NoHeuristicsTable)));
+
+    // admissible, but not much help
+    solver.set_custom_heuristic(|state| Some(if state.is_solved() {0} else {1}));
+
+    for n in 2..=3
+    {
+        for _ in 0..5
+        {
+            let (state, _) = rubiks::RubiksCubeState::rnd_scramble(n, 3);
+            let soln = solver.solve_with_idastar(&state).unwrap();
+            assert!(soln.turns.len() <= 3);
+            assert!(RubiksCubeSolver::verify_solution(&state, &soln));
+            assert_eq!(solver.estimated_distance(&state).unwrap(), if state.is_solved() {0} else {1});
+        }
+    }
+}
+
+#[test]
+fn test_solver_clone_debug()
+{
+    let mut solver = RubiksCubeSolver::new();
+    assert_eq!(format!("{:?}", solver), "RubiksCubeSolver { heuristic_table: None, custom_heuristic: false, cancel: None }");
+
+    solver.add_heuristics_table(HeuristicsTables::new());
+    solver.set_custom_heuristic(|state| Some(if state.is_solved() {0} else {1}));
+    assert_eq!(format!("{:?}", solver), "RubiksCubeSolver { heuristic_table: Some(HeuristicsTables { corners: false }), custom_heuristic: true, cancel: None }");
+
+    // the cancel flag shows its value
+    let cancel = Arc::new(AtomicBool::new(false));
+    solver.set_cancel_flag(cancel.clone());
+    assert!(format!("{:?}", solver).ends_with("custom_heuristic: true, cancel: Some(false) }"));
+    cancel.store(true, Ordering::Relaxed);
+    assert!(format!("{:?}", solver).ends_with("custom_heuristic: true, cancel: Some(true) }"));
+    cancel.store(false, Ordering::Relaxed);
+
+    // the clone shares the table, the custom heuristic and the cancel flag
+    let solver2 = solver.clone();
+    assert!(Arc::ptr_eq(solver.heuristic_table.as_ref().unwrap(), solver2.heuristic_table.as_ref().unwrap()));
+    assert!(Arc::ptr_eq(solver.cancel.as_ref().unwrap(), solver2.cancel.as_ref().unwrap()));
+    assert_eq!(format!("{:?}", solver), format!("{:?}", solver2));
+
+    assert_eq!(format!("{:?}", test_heuristics_table()), "HeuristicsTables { corners: true, corner_entries: 3674160, corner_max_distance: 14 }");
+
+    let (state, _) = rubiks::RubiksCubeState::rnd_scramble(3, 2);
+    assert!(RubiksCubeSolver::verify_solution(&state, &solver2.solve_with_idastar(&state).unwrap()));
+}
+
+/// The corner heuristics table and the `(depth, count)` progress calls from building it.
+#[cfg(test)]
+type TableAndProgress = (Arc<HeuristicsTables>, Vec<(u8, usize)>);
+
+/// Builds the corner heuristics table once for all the tests that need it, it takes a while.
+/// Also gives the progress calls from building it.
+#[cfg(test)]
+fn test_heuristics_table_and_progress() -> &'static TableAndProgress
+{
+    static TABLE: std::sync::OnceLock<TableAndProgress> = std::sync::OnceLock::new();
+
+    TABLE.get_or_init(||
+    {
+        let mut progress_calls = vec![];
+        let mut ht = HeuristicsTables::new();
+        ht.calc_corner_heuristics_table_with_progress(100000, |depth, count| progress_calls.push((depth, count)));
+        (Arc::new(ht), progress_calls)
+    })
+}
+
+#[cfg(test)]
+fn test_heuristics_table() -> Arc<HeuristicsTables>
+{
+    test_heuristics_table_and_progress().0.clone()
+}
+
+#[test]
+fn test_corner_table_progress()
+{
+    let progress_calls = &test_heuristics_table_and_progress().1;
+
+    assert_eq!(progress_calls.len(), 3674160 / 100000);
+    for (i, &(depth, count)) in progress_calls.iter().enumerate()
+    {
+        assert_eq!(count, (i + 1) * 100000);
+        assert!(depth <= 14);
+        if i > 0
+        {
+            assert!(depth >= progress_calls[i - 1].0);
+        }
+    }
+}
+
+#[test]
+fn test_shared_table()
+{
+    let table = test_heuristics_table();
+    let states: Vec<rubiks::RubiksCubeState> = (0..10).map(|i| rubiks::RubiksCubeState::rnd_scramble(2 + i % 2, 6).0).collect();
+
+    let handles: Vec<_> = (0..2).map(|_|
+    {
+        let solver = RubiksCubeSolver::from_shared_table(table.clone());
+        let states = states.clone();
+        std::thread::spawn(move || states.iter().map(|state| solver.solve_with_idastar(state).unwrap()).collect::<Vec<rubiks::Move>>())
+    }).collect();
+
+    let solns: Vec<Vec<rubiks::Move>> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+    for (i, state) in states.iter().enumerate()
+    {
+        assert!(RubiksCubeSolver::verify_solution(state, &solns[0][i]));
+        assert_eq!(solns[0][i].turns.len(), solns[1][i].turns.len());
+        assert_eq!(solns[0][i], solns[1][i]);
+    }
+}
+
+#[test]
+fn test_idastar_cycle_detection()
+{
+    // a weak heuristic leaves room in the bound for the search to go in cycles
+    let mut solver = RubiksCubeSolver::new();
+    solver.set_custom_heuristic(|state| Some(if state.is_solved() {0} else {1}));
+
+    let mut states: Vec<rubiks::RubiksCubeState> = (0..10).map(|i| rubiks::RubiksCubeState::rnd_scramble(2 + i % 2, 4).0).collect();
+    // on a 2x2x2, `R L'` only rotates the cube so the search can get back to where it started in 2 turns
+    let mut r_u_f = rubiks::RubiksCubeState::std_solved_nxnxn(2);
+    r_u_f.do_move(&rubiks::Move{turns: [rubiks::Face::Right, rubiks::Face::Up, rubiks::Face::Front].iter()
+        .map(|&face| rubiks::Turn::FaceBased{face, inv: false, num_in: 0, cube_size: 2}).collect()});
+    states.push(r_u_f);
+
+    for state in &states
+    {
+        let (soln, stats) = solver.solve_with_idastar_stats(state).unwrap();
+        let (soln_no_detect, stats_no_detect) = solver.idastar(state, false, None, None).unwrap();
+
+        assert!(RubiksCubeSolver::verify_solution(state, &soln));
+        assert_eq!(soln.turns.len(), soln_no_detect.turns.len());
+        assert!(stats.nodes <= stats_no_detect.nodes);
+        assert_eq!(stats_no_detect.cycles_pruned, 0);
+    }
+
+    let (_, stats) = solver.solve_with_idastar_stats(&states[10]).unwrap();
+    let (_, stats_no_detect) = solver.idastar(&states[10], false, None, None).unwrap();
+    assert!(stats.cycles_pruned > 0);
+    assert!(stats.nodes < stats_no_detect.nodes);
+}
+
+#[test]
+fn test_solve_from_scramble()
+{
+    let solver = RubiksCubeSolver::from_shared_table(test_heuristics_table());
+
+    for i in 0..10
+    {
+        let n = 2 + i % 2;
+        let scramble = rubiks::Move::rnd_move(n, 6);
+        let soln = solver.solve_from_scramble(&scramble).unwrap();
+
+        assert!(soln.turns.len() <= scramble.turns.len());
+        let mut state = rubiks::RubiksCubeState::std_solved_nxnxn(n);
+        state.do_move(&scramble);
+        assert!(RubiksCubeSolver::verify_solution(&state, &soln));
+    }
+
+    assert!(matches!(solver.solve_from_scramble(&rubiks::Move::empty()), Err(RubikSolveError::BadInput)));
+    let mixed = rubiks::Move::rnd_move(2, 2) * rubiks::Move::rnd_move(3, 2);
+    assert!(matches!(solver.solve_from_scramble(&mixed), Err(RubikSolveError::BadInput)));
+}
+
+#[test]
+fn test_move_between()
+{
+    let solver = RubiksCubeSolver::from_shared_table(test_heuristics_table());
+
+    for i in 0..10
+    {
+        let n = 2 + i % 2;
+        let (from, _) = rubiks::RubiksCubeState::rnd_scramble(n, 4);
+        let (to, _) = rubiks::RubiksCubeState::rnd_scramble(n, 4);
+
+        let between = rubiks::Move::between(&from, &to, &solver).unwrap();
+        let mut state = from.clone();
+        state.do_move(&between);
+        assert_eq!(state, to);
+    }
+
+    // a whole cube rotation of a 2x2x2 is still reachable
+    let (from, _) = rubiks::RubiksCubeState::rnd_scramble(2, 4);
+    let mut to = from.clone();
+    to.rotate_cube(rubiks::Axis::X);
+    to.rotate_cube(rubiks::Axis::Y);
+    let between = rubiks::Move::between(&from, &to, &solver).unwrap();
+    let mut state = from.clone();
+    state.do_move(&between);
+    assert_eq!(state, to);
+
+    let (other_size, _) = rubiks::RubiksCubeState::rnd_scramble(3, 4);
+    assert!(matches!(solver.solve_to(&from, &other_size, 14), Err(RubikSolveError::BadInput)));
+}
+
+#[test]
+fn test_table_kinds()
+{
+    assert_eq!(HeuristicsTables::new().kinds(), vec![]);
+
+    let table = test_heuristics_table();
+    assert_eq!(table.kinds(), vec![TableKind::Corners]);
+    assert!(table.has(TableKind::Corners));
+    assert!(!table.has(TableKind::Edges));
+
+    let solver = RubiksCubeSolver::from_shared_table(table);
+    let (state, _) = rubiks::RubiksCubeState::rnd_scramble(3, 20);
+    assert!(solver.table_heuristic(&state, TableKind::Corners).unwrap() <= 14);
+    assert!(matches!(solver.table_heuristic(&state, TableKind::Edges), Err(RubikSolveError::NoHeuristicsTable)));
+    assert!(matches!(RubiksCubeSolver::new().table_heuristic(&state, TableKind::Corners), Err(RubikSolveError::NoHeuristicsTable)));
+}
+
+#[test]
+fn test_exact_2x2_distance()
+{
+    let table = test_heuristics_table();
+    let solver = RubiksCubeSolver::from_shared_table(table.clone());
+
+    assert_eq!(table.exact_2x2_distance(&rubiks::RubiksCubeState::std_solved_nxnxn(2)), Some(0));
+    assert_eq!(table.exact_2x2_distance(&rubiks::RubiksCubeState::std_solved_nxnxn(3)), None);
+    assert_eq!(HeuristicsTables::new().exact_2x2_distance(&rubiks::RubiksCubeState::std_solved_nxnxn(2)), None);
+
+    for _ in 0..50
+    {
+        let (state, _) = rubiks::RubiksCubeState::rnd_scramble(2, 100);
+        let distance = table.exact_2x2_distance(&state).unwrap() as usize;
+
+        let greedy = solver.solver_2x2x2_with_heuristics_table(&state).unwrap();
+        assert!(RubiksCubeSolver::verify_solution(&state, &greedy));
+        assert_eq!(greedy.turns.len(), distance);
+        assert_eq!(solver.solve_with_idastar(&state).unwrap().turns.len(), distance);
+    }
+}
+
+#[test]
+fn test_solve_traced()
+{
+    let solver = RubiksCubeSolver::from_shared_table(test_heuristics_table());
+
+    for i in 0..10
+    {
+        let (state, _) = rubiks::RubiksCubeState::rnd_scramble(2 + i % 2, 6);
+        let (soln, trace) = solver.solve_with_idastar_traced(&state).unwrap();
+
+        assert!(RubiksCubeSolver::verify_solution(&state, &soln));
+        assert_eq!(trace.iter().map(|t| t.turn).collect::<Vec<rubiks::Turn>>(), soln.turns);
+        for (j, t) in trace.iter().enumerate()
+        {
+            let (before, after) = (t.heuristic_before.unwrap(), t.heuristic_after.unwrap());
+            // a turn moves the corners at most one turn closer or further, and the estimate is never more than what is left
+            assert!(before <= after + 1 && after <= before + 1);
+            assert!(before <= soln.turns.len() - j);
+            if j > 0
+            {
+                assert_eq!(trace[j - 1].heuristic_after, t.heuristic_before);
+            }
+        }
+        assert_eq!(trace.last().map_or(Some(0), |t| t.heuristic_after), Some(0));
+    }
+
+    let (_, trace) = solver.solve_with_idastar_traced(&rubiks::RubiksCubeState::std_solved_nxnxn(3)).unwrap();
+    assert!(trace.is_empty());
+}
+
+#[test]
+fn test_optimal_distance()
+{
+    let table = test_heuristics_table();
+    let solver = RubiksCubeSolver::from_shared_table(table.clone());
+
+    for _ in 0..50
+    {
+        let (state, _) = rubiks::RubiksCubeState::rnd_scramble(2, 100);
+        assert_eq!(solver.optimal_distance(&state).unwrap(), table.exact_2x2_distance(&state).unwrap() as usize);
+    }
+
+    for _ in 0..5
+    {
+        let (state, _) = rubiks::RubiksCubeState::rnd_scramble(3, 6);
+        assert_eq!(solver.optimal_distance(&state).unwrap(), solver.solve_with_idastar(&state).unwrap().turns.len());
+    }
+
+    assert_eq!(solver.optimal_distance(&rubiks::RubiksCubeState::std_solved_nxnxn(3)).unwrap(), 0);
+}
+
+#[test]
+fn test_solve_verified()
+{
+    let solver = RubiksCubeSolver::from_shared_table(test_heuristics_table());
+
+    for &n in [2, 3].iter()
+    {
+        for _ in 0..5
+        {
+            let (state, _) = rubiks::RubiksCubeState::rnd_scramble(n, 6);
+            let (soln, solved) = solver.solve_verified(&state).unwrap();
+            assert!(solved.is_solved());
+            assert_eq!(solved, state.do_move_copy(&soln));
+        }
+    }
+
+    let (soln, solved) = solver.solve_verified(&rubiks::RubiksCubeState::std_solved_nxnxn(3)).unwrap();
+    assert!(soln.turns.is_empty() && solved.is_solved());
+}
+
+#[test]
+fn test_rnd_at_distance()
+{
+    let table = test_heuristics_table();
+    let solver = RubiksCubeSolver::from_shared_table(table.clone());
+
+    for distance in 0..12
+    {
+        let state = rubiks::RubiksCubeState::rnd_at_distance(2, distance, distance as u64, &solver);
+        assert_eq!(table.exact_2x2_distance(&state).unwrap() as usize, distance);
+        assert_eq!(state, rubiks::RubiksCubeState::rnd_at_distance(2, distance, distance as u64, &solver));
+    }
+
+    let state = rubiks::RubiksCubeState::rnd_at_distance(3, 3, 7, &solver);
+    assert_eq!(solver.optimal_distance(&state).unwrap(), 3);
+}
+
+#[test]
+fn test_generate_dataset()
+{
+    let table = test_heuristics_table();
+    let solver = RubiksCubeSolver::from_shared_table(table.clone());
+
+    for &(size, count) in [(1, 3), (2, 20), (3, 2), (4, 5), (5, 5)].iter()
+    {
+        let dataset = solver.generate_dataset(size, count, 42);
+        assert_eq!(dataset.len(), count);
+        for (state, soln) in dataset.iter()
+        {
+            assert_eq!(state.size(), size);
+            assert!(RubiksCubeSolver::verify_solution(state, soln));
+            if size == 2
+            {
+                assert_eq!(soln.turns.len(), table.exact_2x2_distance(state).unwrap() as usize);
+            }
+        }
+
+        let again = solver.generate_dataset(size, count, 42);
+        assert!(dataset.iter().zip(again.iter()).all(|((state, _), (state2, _))| state == state2));
+    }
+
+    // no table to solve with, so the scramble is inverted
+    for (state, soln) in RubiksCubeSolver::new().generate_dataset(3, 3, 1)
+    {
+        assert!(RubiksCubeSolver::verify_solution(&state, &soln));
+    }
+}
+
+#[test]
+fn test_solve_corners_only()
+{
+    let table = test_heuristics_table();
+    let solver = RubiksCubeSolver::from_shared_table(table.clone());
+
+    for n in 2..7
+    {
+        for _ in 0..5
+        {
+            let (state, _) = rubiks::RubiksCubeState::rnd_scramble(n, 40);
+            let soln = solver.solve_corners_only(&state).unwrap();
+            assert!(soln.turns.iter().all(|turn| turn.cube_size() == n));
+            assert_eq!(soln.turns.len(), table.exact_2x2_distance(&state.from_corners_to_2x2x2()).unwrap() as usize);
+
+            let after = state.do_move_copy(&soln);
+            assert!(after.from_corners_to_2x2x2().is_solved());
+            if n == 2
+            {
+                assert!(after.is_solved());
+            }
+        }
+    }
+
+    assert!(matches!(solver.solve_corners_only(&rubiks::RubiksCubeState::std_solved_nxnxn(1)), Err(RubikSolveError::BadInput)));
+    assert!(matches!(RubiksCubeSolver::new().solve_corners_only(&rubiks::RubiksCubeState::std_solved_nxnxn(3)), Err(RubikSolveError::NoHeuristicsTable)));
+}
+
+#[test]
+fn test_solve_capped()
+{
+    let table = test_heuristics_table();
+    let solver = RubiksCubeSolver::from_shared_table(table.clone());
+
+    for _ in 0..10
+    {
+        let (state, _) = rubiks::RubiksCubeState::rnd_scramble(2, 100);
+        let distance = table.exact_2x2_distance(&state).unwrap() as usize;
+
+        let soln = solver.solve_capped(&state, distance).unwrap();
+        assert_eq!(soln.turns.len(), distance);
+        assert!(RubiksCubeSolver::verify_solution(&state, &soln));
+
+        if distance >= 2
+        {
+            let cap = distance - 1;
+            match solver.solve_capped(&state, cap)
+            {
+                Err(RubikSolveError::BestEffort(best)) =>
+                {
+                    assert!(best.turns.len() <= cap);
+                    // the corner table is exact for the 2x2x2, so at least the first turn of a solution was found
+                    assert!((table.exact_2x2_distance(&state.do_move_copy(&best)).unwrap() as usize) < distance);
+                },
+                other => panic!("expected BestEffort, got {:?}", other),
+            }
+        }
+    }
+
+    let (state, _) = rubiks::RubiksCubeState::rnd_scramble(3, 5);
+    let soln = solver.solve_capped(&state, 5).unwrap();
+    assert!(soln.turns.len() <= 5 && RubiksCubeSolver::verify_solution(&state, &soln));
+    assert!(solver.solve_capped(&rubiks::RubiksCubeState::std_solved_nxnxn(3), 0).unwrap().turns.is_empty());
+}
+
+#[test]
+fn test_solutions_iter()
+{
+    let table = test_heuristics_table();
+    let solver = RubiksCubeSolver::from_shared_table(table.clone());
+
+    for _ in 0..5
+    {
+        let (state, _) = rubiks::RubiksCubeState::rnd_scramble(2, 100);
+        let distance = table.exact_2x2_distance(&state).unwrap() as usize;
+
+        // all the solutions up to 14 turns would take forever, so this only works if the search stops
+        let solns: Vec<rubiks::Move> = solver.solutions_iter(&state, 14).take(3).collect();
+        assert_eq!(solns.len(), 3);
+        assert_eq!(solns[0].turns.len(), distance);
+        for (i, soln) in solns.iter().enumerate()
+        {
+            assert!(RubiksCubeSolver::verify_solution(&state, soln));
+            if i > 0
+            {
+                assert!(soln.turns.len() >= solns[i - 1].turns.len());
+                assert!(soln.turns.len() != solns[i - 1].turns.len() || soln.turns != solns[i - 1].turns);
+            }
+        }
+    }
+
+    let (state, _) = rubiks::RubiksCubeState::rnd_scramble(3, 3);
+    let soln = solver.solutions_iter(&state, 3).next().unwrap();
+    assert!(RubiksCubeSolver::verify_solution(&state, &soln));
+
+    let solved = rubiks::RubiksCubeState::std_solved_nxnxn(3);
+    assert_eq!(solver.solutions_iter(&solved, 5).collect::<Vec<rubiks::Move>>(), vec![rubiks::Move::empty()]);
+
+    // R U can't be solved in 1 turn
+    let mut r_u = rubiks::RubiksCubeState::std_solved_nxnxn(3);
+    r_u.do_move(&rubiks::Move::from_notation("R U", 3).unwrap());
+    assert_eq!(solver.solutions_iter(&r_u, 1).count(), 0);
+    assert_eq!(solver.solutions_iter(&r_u, 2).count(), 1);
+}
+
+#[test]
+fn test_compare()
+{
+    let solver = RubiksCubeSolver::from_shared_table(test_heuristics_table());
+    let algos = [SolveAlgo::Dpll, SolveAlgo::IdaStar, SolveAlgo::Iddfs];
+
+    for size in 2..=3
+    {
+        let reports = solver.compare(&algos, size, 5, 4, 1234);
+        assert_eq!(reports.len(), 3);
+
+        for (report, &algo) in reports.iter().zip(algos.iter())
+        {
+            assert_eq!(report.algo, algo);
+            assert_eq!(report.solved, 5);
+            assert_eq!(report.failed, 0);
+            assert!(report.mean_turns() <= 4.0);
+            assert_eq!(report.solution_lengths.iter().sum::<usize>(), report.total_turns);
+            assert_eq!(report.length_histogram().values().sum::<usize>(), 5);
+        }
+
+        // ida* and iddfs both give optimal solutions
+        assert_eq!(reports[1].total_turns, reports[2].total_turns);
+        assert!(reports[1].total_turns <= reports[0].total_turns);
+    }
+}
+
+#[test]
+fn test_cancel_flag()
+{
+    let cancel = Arc::new(AtomicBool::new(false));
+    let mut solver = RubiksCubeSolver::new();
+    solver.set_cancel_flag(cancel.clone());
+
+    let canceller = std::thread::spawn(move ||
+    {
+        std::thread::sleep(Duration::from_millis(50));
+        cancel.store(true, Ordering::Relaxed);
+    });
+
+    // there are no tables for pruning, so this would search for a very long time
+    let t0 = Instant::now();
+    let mut long_state = rubiks::RubiksCubeState::std_solved_nxnxn(3);
+    long_state.do_move(&rubiks::Move::rnd_move(3, 100));
+    assert!(matches!(solver.solve_dpll(&long_state, 15), Err(RubikSolveError::Cancelled)));
+    assert!(t0.elapsed() < Duration::from_secs(5));
+    canceller.join().unwrap();
+
+    // the flag stays set until it is cleared
+    let state = rubiks::RubiksCubeState::scrambled(3, "R U").unwrap();
+    assert!(matches!(solver.solve_dpll(&state, 2), Err(RubikSolveError::Cancelled)));
+    assert_eq!(solver.solutions_iter(&state, 2).next(), None);
+    solver.cancel.as_ref().unwrap().store(false, Ordering::Relaxed);
+    assert_eq!(solver.solve_dpll(&state, 2).unwrap().turns.len(), 2);
+    assert_eq!(solver.solutions_iter(&state, 2).next().unwrap().turns.len(), 2);
+}
+
+#[test]
+fn test_gods_number()
+{
+    assert_eq!(gods_number(1, Metric::Quarter), Some(0));
+    assert_eq!(gods_number(2, Metric::Quarter), Some(14));
+    assert_eq!(gods_number(2, Metric::Half), Some(11));
+    assert_eq!(gods_number(3, Metric::Quarter), Some(26));
+    assert_eq!(gods_number(3, Metric::Half), Some(20));
+    assert_eq!(gods_number(4, Metric::Quarter), None);
+    assert_eq!(gods_number(0, Metric::Half), None);
+
+    // the corner table is the 2x2x2, so it goes up to god's number
+    let table = test_heuristics_table();
+    assert_eq!(table.corners.as_ref().unwrap().values().max().map(|&d| d as usize), gods_number(2, Metric::Quarter));
+}
+
+#[test]
+fn test_dpll_heuristics()
+{
+    let states: Vec<rubiks::RubiksCubeState> = (0..3).map(|_| rubiks::RubiksCubeState::rnd_scramble(3, 4).0).collect();
+    let check_solves = |solver: &RubiksCubeSolver|
+    {
+        for state in states.iter()
+        {
+            let soln = solver.solve_dpll(state, 5).unwrap();
+            assert!(soln.turns.len() <= 5);
+            assert!(RubiksCubeSolver::verify_solution(state, &soln));
+        }
+    };
+
+    let mut no_heuristics = RubiksCubeSolver::new();
+    let mut with_table = RubiksCubeSolver::from_shared_table(test_heuristics_table());
+    assert_eq!(no_heuristics.max_heuristic(), Some(0));
+    assert_eq!(with_table.max_heuristic(), Some(14));
+    check_solves(&no_heuristics);
+    check_solves(&with_table);
+
+    // custom heuristics have no known limit so they are always checked
+    no_heuristics.set_custom_heuristic(|state| Some(if state.is_solved() {0} else {1}));
+    with_table.set_custom_heuristic(|state| Some(if state.is_solved() {0} else {1}));
+    assert_eq!(no_heuristics.max_heuristic(), None);
+    assert_eq!(with_table.max_heuristic(), None);
+    check_solves(&no_heuristics);
+    check_solves(&with_table);
+}
+
+#[test]
+fn test_solve_with_generators()
+{
+    let solver = RubiksCubeSolver::new();
+    let half_turns: Vec<rubiks::Move> = ["U2", "L2", "F2", "R2", "B2", "D2"].iter()
+        .map(|notation| rubiks::Move::from_notation(notation, 3).unwrap()).collect();
+
+    let scramble = rubiks::Move::concat(&(0..8).map(|i| half_turns[(i * 7 + 3) % 6].clone()).collect::<Vec<rubiks::Move>>());
+    let state = rubiks::RubiksCubeState::std_solved_nxnxn(3).with_move(&scramble);
+    let soln = solver.solve_with_generators(&state, &half_turns, 8).unwrap();
+    assert!(RubiksCubeSolver::verify_solution(&state, &soln));
+    assert!(soln.to_htm().iter().all(|htm_turn| htm_turn.amount == 2));
+
+    // <U2, R2> only has 12 states, so a quarter turn is never reached no matter how big k is
+    let u2_r2 = [half_turns[0].clone(), half_turns[3].clone()];
+    let r = rubiks::RubiksCubeState::std_solved_nxnxn(3).with_move(&rubiks::Move::from_notation("R", 3).unwrap());
+    assert!(matches!(solver.solve_with_generators(&r, &u2_r2, 1000), Err(RubikSolveError::Unsolveable)));
+    assert!(matches!(solver.solve_with_generators(&state, &[rubiks::Move::from_notation("R2", 4).unwrap()], 8), Err(RubikSolveError::BadInput)));
+
+    // slice moves work too, like turning both middle layers of a 4x4x4
+    let slice = rubiks::Move::from_notation("2R 2L'", 4).unwrap();
+    let state = rubiks::RubiksCubeState::std_solved_nxnxn(4).with_move(&slice).with_move(&slice);
+    assert_eq!(solver.solve_with_generators(&state, &[slice.clone(), slice.invert()], 4).unwrap().turns.len(), 4);
+}
+
+#[test]
+fn test_corner_table()
+{
+    assert!(HeuristicsTables::new().corner_table().is_none());
+
+    let table = test_heuristics_table();
+    let corner_table = table.corner_table().unwrap();
+    assert_eq!(corner_table.len(), NUM_2X2X2_STATES);
+    // the table is allocated once for all the states, not grown to twice what it needs
+    assert!(corner_table.capacity() < 2 * NUM_2X2X2_STATES);
+    assert_eq!(corner_table.values().max(), Some(&14));
+    assert_eq!(corner_table.get(&rubiks::CanonicalState::new(&rubiks::RubiksCubeState::std_solved_nxnxn(2))), Some(&0));
+    assert_eq!(corner_table.values().filter(|&&d| d == 1).count(), 6);
+}
+
+#[test]
+fn test_corner_distance()
+{
+    let table = test_heuristics_table();
+    assert_eq!(HeuristicsTables::new().corner_distance(&rubiks::RubiksCubeState::std_solved_nxnxn(3)), None);
+
+    let states: Vec<rubiks::RubiksCubeState> = (0..300).map(|i| rubiks::RubiksCubeState::rnd_scramble(2 + i % 5, 30).0).collect();
+    let corner_table = table.corner_table().unwrap();
+
+    let chain: Vec<Option<u8>> = states.iter().map(|state| corner_table.get(&rubiks::CanonicalState::new(&state.from_corners_to_2x2x2())).copied()).collect();
+    let distances: Vec<Option<u8>> = states.iter().map(|state| table.corner_distance(state)).collect();
+
+    assert_eq!(distances, chain);
+    assert!(distances.iter().all(|d| d.is_some()));
+}
+
+#[cfg(feature = "bench")]
+#[test]
+fn test_corner_distance_speed()
+{
+    let table = test_heuristics_table();
+    let corner_table = table.corner_table().unwrap();
+    let states: Vec<rubiks::RubiksCubeState> = (0..3000).map(|i| rubiks::RubiksCubeState::rnd_scramble(2 + i % 5, 30).0).collect();
+
+    let t_chain = Instant::now();
+    for state in &states
+    {
+        std::hint::black_box(corner_table.get(&rubiks::CanonicalState::new(&state.from_corners_to_2x2x2())));
+    }
+    let t_chain = t_chain.elapsed();
+
+    let t_distance = Instant::now();
+    for state in &states
+    {
+        std::hint::black_box(table.corner_distance(state));
+    }
+    let t_distance = t_distance.elapsed();
+
+    // corner_distance skips making the 2x2x2 and rotating it, it was about twice as fast
+    println!("corner lookups: {:?} with corner_distance, {:?} with from_corners_to_2x2x2", t_distance, t_chain);
+    assert!(t_distance < t_chain);
+}
+
+#[test]
+fn test_solve_phases()
+{
+    let solved = rubiks::RubiksCubeState::std_solved_nxnxn(3);
+    let phases: Vec<Box<dyn PhaseSolver>> = ThistlethwaitePhase::all().iter()
+        .map(|&phase| Box::new(phase) as Box<dyn PhaseSolver>).collect();
+    assert!(ThistlethwaitePhase::all().iter().all(|phase| phase.goal(&solved)));
+
+    let solver = RubiksCubeSolver::new();
+    for scramble in ["R U F", "L D' B", "B L U"].iter()
+    {
+        let state = solved.do_move_copy(&rubiks::Move::from_notation(scramble, 3).unwrap());
+        let soln = solver.solve_phases(&state, &phases).unwrap();
+        assert!(state.do_move_copy(&soln).is_solved());
+    }
+
+    // R keeps the edges but twists corners, F flips edges
+    let r = solved.do_move_copy(&rubiks::Move::from_notation("R", 3).unwrap());
+    assert!(ThistlethwaitePhase::EdgeOrientation.goal(&r) && !ThistlethwaitePhase::CornerOrientation.goal(&r));
+    let f = solved.do_move_copy(&rubiks::Move::from_notation("F", 3).unwrap());
+    assert!(!ThistlethwaitePhase::EdgeOrientation.goal(&f));
+
+    // one phase of our own, the up face is any one color
+    struct UpFace;
+    impl PhaseSolver for UpFace
+    {
+        fn goal(&self, state: &rubiks::RubiksCubeState) -> bool
+        {
+            state.face_grid(rubiks::Face::Up).iter().flatten().all(|&c| c == state.data_at(0))
+        }
+
+        fn moves(&self, state: &rubiks::RubiksCubeState) -> Vec<rubiks::Move>
+        {
+            state.all_turns().into_iter().map(|turn| turn.as_move()).collect()
+        }
+    }
+    let state = solved.do_move_copy(&rubiks::Move::from_notation("R F", 3).unwrap());
+    let soln = solver.solve_phases(&state, &[Box::new(UpFace) as Box<dyn PhaseSolver>]).unwrap();
+    assert!(UpFace.goal(&state.do_move_copy(&soln)));
+    assert!(soln.turns.len() <= 2);
+}
+
+#[test]
+fn test_solve_thistlethwaite()
+{
+    let solver = RubiksCubeSolver::new();
+    let mut rng = rand::rngs::StdRng::seed_from_u64(1473);
+    // the most moves each phase can need
+    let max_phase_moves = [7, 10, 13, 15];
+
+    for _ in 0..100
+    {
+        let scramble = rubiks::Move::rnd_move_with_rng(3, 40, &mut rng);
+        let state = rubiks::RubiksCubeState::std_solved_nxnxn(3).do_move_copy(&scramble);
+
+        let soln = solver.solve_thistlethwaite(&state).unwrap();
+        assert!(state.do_move_copy(&soln).is_solved());
+        assert!(soln.htm() <= 45);
+
+        // each phase ends in its goal, which is also the goal of the phases before it
+        let mut phase_state = state.clone();
+        for (i, &phase) in ThistlethwaitePhase::all().iter().enumerate()
+        {
+            assert_eq!(phase.heuristic(&phase_state) == 0, phase.goal(&phase_state));
+            let phase_soln = solver.solve_phase(&phase_state, &phase).unwrap();
+            assert!(phase_soln.htm() <= max_phase_moves[i]);
+            phase_state.do_move(&phase_soln);
+            assert!(ThistlethwaitePhase::all()[..=i].iter().all(|earlier| earlier.goal(&phase_state)));
+        }
+        assert!(phase_state.is_solved());
+    }
+
+    assert!(matches!(solver.solve_thistlethwaite(&rubiks::RubiksCubeState::std_solved_nxnxn(2)), Err(RubikSolveError::BadInput)));
+    // the UF edge flipped
+    let flipped = rubiks::RubiksCubeStateBuilder::new(3)
+        .set(rubiks::Face::Up, 2, 1, rubiks::Face::Front.standard_color())
+        .set(rubiks::Face::Front, 0, 1, rubiks::Face::Up.standard_color())
+        .build().unwrap();
+    assert!(matches!(solver.solve_thistlethwaite(&flipped), Err(RubikSolveError::Unsolveable)));
+}
+
+#[test]
+fn test_length_histogram()
+{
+    let solver = RubiksCubeSolver::from_shared_table(test_heuristics_table());
+
+    let histogram = solver.length_histogram(2, 20, 1474);
+    assert_eq!(histogram.values().sum::<usize>(), 20);
+    assert!(histogram.keys().all(|&len| len <= gods_number(2, Metric::Quarter).unwrap()));
+    assert_eq!(solver.length_histogram(2, 20, 1474), histogram);
+
+    let histogram = solver.length_histogram(3, 5, 1474);
+    assert_eq!(histogram.values().sum::<usize>(), 5);
+    // thistlethwaite is at most 45 half turns
+    assert!(histogram.keys().all(|&len| len <= 90));
+
+    assert!(solver.length_histogram(4, 5, 1474).is_empty());
+    assert!(solver.length_histogram(1, 5, 1474).is_empty());
+    assert!(solver.length_histogram(0, 5, 1474).is_empty());
+}
+
+#[test]
+fn test_solve_with_fallback()
+{
+    // without a table the optimal solver can't run
+    let solver = RubiksCubeSolver::new();
+    let state = rubiks::RubiksCubeState::scrambled(3, "R U2 F' L D B2 R' U F2 D' L2 B").unwrap();
+    let (soln, used) = solver.solve_with_fallback(&state);
+    assert_eq!(used, SolverUsed::Thistlethwaite);
+    assert!(RubiksCubeSolver::verify_solution(&state, &soln.unwrap()));
+
+    let (soln, used) = solver.solve_with_fallback(&rubiks::RubiksCubeState::scrambled(4, "R U").unwrap());
+    assert!(matches!(soln, Err(RubikSolveError::NoHeuristicsTable)));
+    assert_eq!(used, SolverUsed::Nothing);
+
+    let solver = RubiksCubeSolver::from_shared_table(test_heuristics_table());
+    let state = rubiks::RubiksCubeState::scrambled(3, "R U F'").unwrap();
+    let (soln, used) = solver.solve_with_fallback(&state);
+    assert_eq!(used, SolverUsed::Optimal);
+    let soln = soln.unwrap();
+    assert!(RubiksCubeSolver::verify_solution(&state, &soln));
+    assert_eq!(soln.turns.len(), 3);
+}
+
+#[test]
+fn test_solve_opt()
+{
+    let solver = RubiksCubeSolver::from_shared_table(test_heuristics_table());
+    let state = rubiks::RubiksCubeState::scrambled(3, "R U F'").unwrap();
+
+    for soln in [solver.solve_opt(&state, 3), solver.solve_dpll_opt(&state, 3)].iter()
+    {
+        assert!(RubiksCubeSolver::verify_solution(&state, soln.as_ref().unwrap()));
+    }
+    // past the depth limit
+    assert!(solver.solve_opt(&state, 2).is_none());
+    assert!(solver.solve_dpll_opt(&state, 2).is_none());
+
+    // no table
+    assert!(RubiksCubeSolver::new().solve_opt(&state, 3).is_none());
+}
+
+#[test]
+fn test_solve_observed()
+{
+    let solver = RubiksCubeSolver::from_shared_table(test_heuristics_table());
+    let state = rubiks::RubiksCubeState::scrambled(3, "R U F' D2").unwrap();
+
+    let mut nodes: Vec<(usize, Option<rubiks::Turn>, usize)> = vec![];
+    let (soln, stats) = solver.solve_with_idastar_observed(&state, &mut |depth, turn, f| nodes.push((depth, turn, f))).unwrap();
+    assert!(RubiksCubeSolver::verify_solution(&state, &soln));
+    assert_eq!(nodes.len(), stats.nodes);
+
+    // each node is one deeper than its parent, the roots are the cube given
+    assert_eq!(nodes[0], (0, None, nodes[0].2));
+    for pair in nodes.windows(2)
+    {
+        assert!(pair[1].0 <= pair[0].0 + 1);
+        assert_eq!(pair[1].0 == 0, pair[1].1.is_none());
+    }
+    // the search ends at the last turn of the solution
+    assert_eq!(nodes.last().unwrap(), &(soln.turns.len(), soln.turns.last().copied(), soln.turns.len()));
+}
+
+/// Counts the allocations made on each thread, so the bench tests can check the search doesn't allocate per node.
+#[cfg(all(test, feature = "bench"))]
+struct CountingAllocator;
+
+#[cfg(all(test, feature = "bench"))]
+thread_local!
+{
+    static ALLOCATIONS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+#[cfg(all(test, feature = "bench"))]
+unsafe impl std::alloc::GlobalAlloc for CountingAllocator
+{
+    unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8
+    {
+        ALLOCATIONS.with(|allocations| allocations.set(allocations.get() + 1));
+        unsafe { std::alloc::System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout)
+    {
+        unsafe { std::alloc::System.dealloc(ptr, layout) }
+    }
+}
+
+#[cfg(all(test, feature = "bench"))]
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[cfg(feature = "bench")]
+#[test]
+fn test_dpll_allocations()
+{
+    let solver = RubiksCubeSolver::new();
+    let allocations = || ALLOCATIONS.with(|allocations| allocations.get());
+
+    // a full search to each depth, so the deeper one goes to about 80 times as many states
+    let state = loop
+    {
+        let (state, _) = rubiks::RubiksCubeState::rnd_scramble(2, 100);
+        if solver.solve_dpll(&state, 6).is_err()
+        {
+            break state;
+        }
+    };
+
+    let before = allocations();
+    assert!(matches!(solver.solve_dpll(&state, 4), Err(RubikSolveError::Unsolveable)));
+    let shallow = allocations() - before;
+
+    let before = allocations();
+    assert!(matches!(solver.solve_dpll(&state, 6), Err(RubikSolveError::Unsolveable)));
+    let deep = allocations() - before;
+
+    // cloning the state for each next turn (and the copy of the face each turn made) allocated for every state searched,
+    // now there are only the few to set up the search and grow the move
+    println!("dpll allocations: {} to depth 4, {} to depth 6", shallow, deep);
+    assert!(deep <= shallow + 2);
+    assert!(deep < 100);
+}
+